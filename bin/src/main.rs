@@ -19,9 +19,21 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
+use std::env;
+use std::process::exit;
+
 mod play;
+mod tournament;
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("match") {
+        if let Err(message) = tournament::run(&args[1..]) {
+            eprintln!("{}", message);
+            exit(1);
+        }
+        return;
+    }
     loop {
         play::menu()
     }
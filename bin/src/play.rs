@@ -18,42 +18,41 @@ LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
+use norts::{play_game, Board, HumanPlayer, Outcome, Rules, Square, Strength, StrengthPlayer};
 use std::io;
 use std::process::exit;
-use norts::Board;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn play_against_engine(engine_player: i8, pgn: &str) {
+pub fn play_against_engine(engine_player: i8, pgn: &str, strength: Strength, rules: Rules) {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let mut engine = StrengthPlayer::new(strength, seed);
+    let mut user = HumanPlayer::new(user_turn).on_opponent_move(|_: &Board, square| {
+        println!("Engine's move: {}\n", square);
+    });
     println!("\n\n\n");
-    let mut pos = Board::parse_pgn(pgn).unwrap();
-    loop {
-        if !pos.is_in_play() {
-            break;
-        }
-        if pos.current_player() == engine_player {
-            engine_turn(&mut pos);
-        } else {
-            user_turn(&mut pos);
-        }
+    let start = Board::parse_pgn_with_rules(pgn, rules).unwrap();
+    let pos = if engine_player == 1 {
+        play_game(&start, &mut engine, &mut user)
+    } else {
+        play_game(&start, &mut user, &mut engine)
     }
+    .unwrap();
     println!("\n\nGame Over!");
     pos.show();
     match pos.situation() {
-        1 => println!("\nX won!\n"),
-        -1 => println!("\nO won!\n"),
-        0 => println!("\nDraw!\n"),
-        _ => (),
+        Outcome::XWon => println!("\nX won!\n"),
+        Outcome::OWon => println!("\nO won!\n"),
+        Outcome::Draw => println!("\nDraw!\n"),
+        Outcome::Ongoing => (),
     }
     println!("Press enter to continue.");
-    io::stdin().read_line(&mut pos.pgn).unwrap();
+    io::stdin().read_line(&mut String::new()).unwrap();
 }
 
-pub fn engine_turn(pos: &mut Board) {
-    let best_move = pos.best_move().unwrap();
-    pos.play(best_move).unwrap();
-    println!("Engine's move: {}\n", best_move);
-}
-
-pub fn user_turn(pos: &mut Board) {
+pub fn user_turn(pos: &Board) -> Square {
     pos.show();
     loop {
         let mut square_str = String::new();
@@ -64,45 +63,63 @@ pub fn user_turn(pos: &mut Board) {
         }
         io::stdin().read_line(&mut square_str).expect("---");
         square_str.pop();
-        if square_str.len() == 1 {
-            if square_str.chars().next().unwrap().is_numeric() {
-                let square: i8 = square_str.parse().unwrap();
-                if pos.is_valid_move(square) {
-                    pos.play(square).unwrap();
-                    break;
-                }
+        if square_str.len() == 1 && square_str.chars().next().unwrap().is_numeric() {
+            let square: i8 = square_str.parse().unwrap();
+            if pos.is_valid_move(square) {
+                return Square::new(square as u8).unwrap();
             }
         }
     }
 }
 
+pub fn choose_engine_player() -> i8 {
+    loop {
+        println!("\n\nShould the engine be X or O? (X goes first)");
+        let mut engine_player_choice = String::new();
+        io::stdin()
+            .read_line(&mut engine_player_choice)
+            .expect("---");
+        if engine_player_choice.to_lowercase() == "x\n" {
+            return 1;
+        } else if engine_player_choice.to_lowercase() == "o\n" {
+            return -1;
+        }
+    }
+}
+
+pub fn choose_strength() -> Strength {
+    loop {
+        println!("\n\nHow strong should the engine be?");
+        println!("[1] - Easy (random moves)");
+        println!("[2] - Medium (looks 2 moves ahead)");
+        println!("[3] - Hard (blunders 1 in 5 moves)");
+        println!("[4] - Perfect (unbeatable)");
+        let mut choice = String::new();
+        io::stdin().read_line(&mut choice).expect("---");
+        match &choice as &str {
+            "1\n" => return Strength::Random,
+            "2\n" => return Strength::DepthLimited(2),
+            "3\n" => return Strength::Blunder(0.2),
+            "4\n" => return Strength::Perfect,
+            _ => {}
+        }
+    }
+}
+
 pub fn menu() {
     loop {
         println!("\n\n\n\nnorts.\n\n");
         println!("[1] - Play against engine");
         println!("[2] - Play from PGN");
-        println!("[3] - Find the best move from PGN\n");
-        println!("[4] - Exit");
+        println!("[3] - Find the best move from PGN");
+        println!("[4] - Play misère against engine (completing a line loses)\n");
+        println!("[5] - Exit");
         let mut choice = String::new();
         io::stdin().read_line(&mut choice).expect("---");
         match &choice as &str {
             "1\n" => {
-                let engine_player: i8;
-                loop {
-                    println!("\n\nShould the engine be X or O? (X goes first)");
-                    let mut engine_player_choice = String::new();
-                    io::stdin()
-                        .read_line(&mut engine_player_choice)
-                        .expect("---");
-                    if engine_player_choice.to_lowercase() == "x\n" {
-                        engine_player = 1;
-                        break;
-                    } else if engine_player_choice.to_lowercase() == "o\n" {
-                        engine_player = -1;
-                        break;
-                    }
-                }
-                play_against_engine(engine_player, "");
+                let engine_player = choose_engine_player();
+                play_against_engine(engine_player, "", choose_strength(), Rules::Standard);
                 break;
             }
 
@@ -117,22 +134,8 @@ pub fn menu() {
                         break 'inside;
                     }
                 }
-                let engine_player: i8;
-                'inside: loop {
-                    println!("\n\nShould the engine be X or O? (X goes first)");
-                    let mut engine_player_choice = String::new();
-                    io::stdin()
-                        .read_line(&mut engine_player_choice)
-                        .expect("---");
-                    if engine_player_choice.to_lowercase() == "x\n" {
-                        engine_player = 1;
-                        break 'inside;
-                    } else if engine_player_choice.to_lowercase() == "o\n" {
-                        engine_player = -1;
-                        break;
-                    }
-                }
-                play_against_engine(engine_player, &*pgn);
+                let engine_player = choose_engine_player();
+                play_against_engine(engine_player, &pgn, choose_strength(), Rules::Standard);
             }
 
             "3\n" => {
@@ -146,14 +149,21 @@ pub fn menu() {
                         break 'inside;
                     }
                 }
-                let mut pos = Board::parse_pgn(&*pgn).unwrap();
+                let mut pos = Board::parse_pgn(&pgn).unwrap();
                 pos.show();
                 println!("Best move: {}", pos.best_move().unwrap());
+                println!("Best line: {}", pos.principal_variation().unwrap());
                 println!("\nPress enter to continue.");
                 io::stdin().read_line(&mut pgn).unwrap();
             }
 
             "4\n" => {
+                let engine_player = choose_engine_player();
+                play_against_engine(engine_player, "", choose_strength(), Rules::Misere);
+                break;
+            }
+
+            "5\n" => {
                 exit(0);
             }
 
@@ -0,0 +1,128 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+use norts::{
+    play_match, Board, Budget, Engine, Mcts, Menace, Rules, Searcher, Strength, StrengthPlayer,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "Usage: norts_bin match <engine> <engine> [--games N] [--openings PGN,PGN,...] [--rules standard|misere|infinite] [--seed N]
+
+Engines:
+  solver        the perfect solver, always taking the first best move
+  perfect       perfect play, choosing randomly between equally good moves
+  random        random legal moves
+  depth:N       looks N moves ahead
+  blunder:P     perfect play, but deliberately blunders with probability P
+  epsilon:P     perfect play, but plays a random move with probability P
+  mcts:N        Monte Carlo Tree Search with N playouts per move
+  menace:N      MENACE matchboxes after N games of self-play under the same rules,
+                not learning during the match";
+
+/// Plays a match between two engines from the command line arguments after `match`
+/// and prints the results from the first engine's point of view.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut engines = Vec::new();
+    let mut games = 100;
+    let mut pgns = vec![String::new()];
+    let mut rules = Rules::Standard;
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("{} needs a value\n\n{}", arg, USAGE))
+        };
+        match arg.as_str() {
+            "--games" => games = value()?.parse().map_err(|_| "--games must be a number")?,
+            "--openings" => pgns = value()?.split(',').map(str::to_owned).collect(),
+            "--rules" => {
+                rules = match value()?.as_str() {
+                    "standard" => Rules::Standard,
+                    "misere" => Rules::Misere,
+                    "infinite" => Rules::Infinite,
+                    other => return Err(format!("Unknown rules {}\n\n{}", other, USAGE)),
+                }
+            }
+            "--seed" => seed = value()?.parse().map_err(|_| "--seed must be a number")?,
+            name => engines.push(name.to_owned()),
+        }
+    }
+    if engines.len() != 2 {
+        return Err(USAGE.to_owned());
+    }
+    let mut openings = Vec::new();
+    for pgn in &pgns {
+        let pos = Board::parse_pgn_with_rules(pgn, rules)
+            .map_err(|_| format!("Invalid opening PGN \"{}\"", pgn))?;
+        if !pos.is_in_play() {
+            return Err(format!("The opening \"{}\" has already finished", pgn));
+        }
+        openings.push(pos);
+    }
+    let mut engine_a = parse_engine(&engines[0], rules, seed)?;
+    let mut engine_b = parse_engine(&engines[1], rules, seed.wrapping_add(1))?;
+    println!("{} vs {}, {} games\n", engines[0], engines[1], games);
+    let result = play_match(engine_a.as_mut(), engine_b.as_mut(), &openings, games);
+    println!("{}", result);
+    Ok(())
+}
+
+fn parse_engine(name: &str, rules: Rules, seed: u64) -> Result<Box<dyn Engine<Board>>, String> {
+    let (kind, parameter) = match name.split_once(':') {
+        Some((kind, parameter)) => (kind, Some(parameter)),
+        None => (name, None),
+    };
+    let number = |parameter: Option<&str>| {
+        parameter
+            .and_then(|parameter| parameter.parse::<f64>().ok())
+            .ok_or(format!("{} needs a number, such as {}:2", kind, kind))
+    };
+    let strength = match kind {
+        "solver" => return Ok(Box::new(Searcher::new())),
+        "mcts" => {
+            let playouts = number(parameter)? as u32;
+            return Ok(Box::new(
+                Mcts::new(Budget::Iterations(playouts)).with_seed(seed),
+            ));
+        }
+        "menace" => {
+            if rules == Rules::Infinite {
+                // marks vanish under infinite rules, which the matchboxes can't see
+                return Err("menace can't play infinite games".to_owned());
+            }
+            let games = number(parameter)? as u32;
+            let mut menace = Menace::new(seed);
+            menace.train_self_play(&Board::with_rules(rules), games);
+            menace.set_learning(false);
+            return Ok(Box::new(menace));
+        }
+        "perfect" => Strength::Perfect,
+        "random" => Strength::Random,
+        "depth" => Strength::DepthLimited(number(parameter)? as u8),
+        "blunder" => Strength::Blunder(number(parameter)?),
+        "epsilon" => Strength::EpsilonOptimal(number(parameter)?),
+        _ => return Err(format!("Unknown engine {}\n\n{}", name, USAGE)),
+    };
+    Ok(Box::new(StrengthPlayer::new(strength, seed)))
+}
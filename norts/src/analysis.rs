@@ -0,0 +1,63 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+/// The result of a move for the player making it, assuming perfect play afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveOutcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// Exact evaluation of a single legal move, see `Board::analyse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveAnalysis {
+    pub outcome: MoveOutcome,
+    /// Number of moves, including this one, until the game ends with perfect play.
+    /// This is 0 for draws under `Rules::Infinite`, which go on until a position repeats.
+    pub moves_to_end: u8,
+}
+
+impl MoveAnalysis {
+    /// Converts an eval returned by the search after playing a move
+    /// into the outcome for the player who made it.
+    /// `num_moves` is the number of moves played before the move being analysed.
+    pub(crate) fn from_eval(eval: i8, num_moves: u8, x_to_move: bool) -> MoveAnalysis {
+        if eval == 0 {
+            // draws only happen once the board is full
+            return MoveAnalysis {
+                outcome: MoveOutcome::Draw,
+                moves_to_end: 9 - num_moves,
+            };
+        }
+        // wins and losses are scored as 100 - the length of the game
+        let game_length = 100 - eval.unsigned_abs();
+        let outcome = if (eval > 0) == x_to_move {
+            MoveOutcome::Win
+        } else {
+            MoveOutcome::Loss
+        };
+        MoveAnalysis {
+            outcome,
+            moves_to_end: game_length - num_moves,
+        }
+    }
+}
@@ -30,9 +30,31 @@ SOFTWARE.
 // 3   4   5
 // 6   7   8
 
+/// The 8 winning lines of a 3x3 board: rows, columns then diagonals.
+pub(crate) const LINES: [u16; 8] = [
+    0b0000_0000_0000_0111,
+    0b0000_0000_0011_1000,
+    0b0000_0001_1100_0000,
+    0b0000_0000_0100_1001,
+    0b0000_0000_1001_0010,
+    0b0000_0001_0010_0100,
+    0b0000_0001_0001_0001,
+    0b0000_0000_0101_0100,
+];
+
+/// Every square of a 3x3 board.
+pub(crate) const FULL: u16 = 0b0000_0001_1111_1111;
+
+/// Returns true if the marks contain a complete line.
+#[inline(always)]
+pub(crate) fn has_line(marks: u16) -> bool {
+    LINES.iter().any(|line| marks & line == *line)
+}
+
 /// Representation of the board using 2 u16 bitboards.
 /// This does not include any safety features and as such may panic,
 /// so dont use this unless you know what you are doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Bitboard {
     pub x_bitboard: u16,
     pub o_bitboard: u16,
@@ -61,7 +83,6 @@ impl Bitboard {
         self.x_bitboard.count_ones() <= self.o_bitboard.count_ones()
     }
 
-
     #[inline(always)]
     pub fn num_moves(&self) -> u8 {
         (self.x_bitboard.count_ones() + self.o_bitboard.count_ones()) as u8
@@ -103,7 +124,7 @@ impl Bitboard {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     #[inline(always)]
@@ -128,7 +149,7 @@ impl Bitboard {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     #[inline(always)]
@@ -137,3 +158,138 @@ impl Bitboard {
             && (self.o_bitboard & (1 << square)) != 1 << square
     }
 }
+
+impl Default for Bitboard {
+    fn default() -> Self {
+        Bitboard::new()
+    }
+}
+
+/// Bitboard with room for up to 256 squares, used by boards larger than 3x3.
+/// Square `n` is stored in bit `n % 64` of word `n / 64`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct WideBitboard {
+    pub words: [u64; 4],
+}
+
+impl WideBitboard {
+    pub const MAX_SQUARES: usize = 256;
+
+    pub fn new() -> WideBitboard {
+        WideBitboard { words: [0; 4] }
+    }
+
+    #[inline(always)]
+    pub fn get(&self, square: usize) -> bool {
+        self.words[square / 64] & (1 << (square % 64)) != 0
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, square: usize) {
+        self.words[square / 64] |= 1 << (square % 64)
+    }
+
+    #[inline(always)]
+    pub fn clear_square(&mut self, square: usize) {
+        self.words[square / 64] &= !(1 << (square % 64))
+    }
+
+    #[inline(always)]
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|word| word.count_ones()).sum()
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.words == [0; 4]
+    }
+
+    /// Returns true if every square set in `other` is also set in `self`.
+    #[inline(always)]
+    pub fn contains_all(&self, other: &WideBitboard) -> bool {
+        (0..4).all(|i| self.words[i] & other.words[i] == other.words[i])
+    }
+
+    /// Returns true if any square is set in both.
+    #[inline(always)]
+    pub fn intersects(&self, other: &WideBitboard) -> bool {
+        (0..4).any(|i| self.words[i] & other.words[i] != 0)
+    }
+
+    #[inline(always)]
+    pub fn union(&self, other: &WideBitboard) -> WideBitboard {
+        WideBitboard {
+            words: [
+                self.words[0] | other.words[0],
+                self.words[1] | other.words[1],
+                self.words[2] | other.words[2],
+                self.words[3] | other.words[3],
+            ],
+        }
+    }
+
+    #[inline(always)]
+    pub fn intersection(&self, other: &WideBitboard) -> WideBitboard {
+        WideBitboard {
+            words: [
+                self.words[0] & other.words[0],
+                self.words[1] & other.words[1],
+                self.words[2] & other.words[2],
+                self.words[3] & other.words[3],
+            ],
+        }
+    }
+
+    /// Returns the squares set in `self` but not in `other`.
+    #[inline(always)]
+    pub fn difference(&self, other: &WideBitboard) -> WideBitboard {
+        WideBitboard {
+            words: [
+                self.words[0] & !other.words[0],
+                self.words[1] & !other.words[1],
+                self.words[2] & !other.words[2],
+                self.words[3] & !other.words[3],
+            ],
+        }
+    }
+
+    /// Iterates over the set squares in ascending order.
+    pub fn squares(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..4).flat_map(move |i| {
+            let mut word = self.words[i];
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// Returns every position reachable in a legal game, for tests which check all of them.
+#[cfg(test)]
+pub(crate) fn legal_positions() -> Vec<Bitboard> {
+    let mut seen = std::collections::HashSet::from([Bitboard::new()]);
+    let mut positions = vec![Bitboard::new()];
+    let mut next = 0;
+    while next < positions.len() {
+        let pos = positions[next];
+        next += 1;
+        if pos.x_won() || pos.o_won() {
+            continue;
+        }
+        for square in 0..9 {
+            if pos.is_legal(square) {
+                let mut child = pos;
+                child.play(square);
+                if seen.insert(child) {
+                    positions.push(child);
+                }
+            }
+        }
+    }
+    positions
+}
@@ -22,8 +22,16 @@ SOFTWARE.
 use std::fmt;
 use std::fmt::Formatter;
 
+use crate::analysis::{MoveAnalysis, MoveOutcome};
 use crate::bitboards::Bitboard;
-use crate::search::search;
+use crate::evaluation::{Evaluation, Outcome, Square};
+use crate::infinite::{self, InfiniteState, REPETITIONS_FOR_DRAW};
+use crate::retrograde::Value;
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::search::{search, search_moves, Searcher};
+use crate::strength::{choose_move, Strength};
+use crate::tablebase::Tablebase;
 
 #[derive(Debug, Clone)]
 pub struct PositionAlreadyConcludedError;
@@ -65,27 +73,46 @@ impl fmt::Display for NoMoveToUndoError {
 }
 /// The main representation of the board for end user interaction.
 /// See methods for usage.
+#[derive(Debug, Clone)]
 pub struct Board {
     /// Binary representation of the position used to optimise performance.
     pub bitboard: Bitboard,
-    ///
+    /// The moves played so far, see `Board::parse_pgn`.
     pub pgn: String,
+    rules: Rules,
 }
 
 impl Board {
     /// Returns a fresh board in the starting position.
     pub fn new() -> Board {
+        Board::with_rules(Rules::Standard)
+    }
+
+    /// Returns a fresh board in the starting position which is played with the given rules,
+    /// such as `Rules::Misere` where completing a line loses.
+    pub fn with_rules(rules: Rules) -> Board {
         Board {
             bitboard: Bitboard::new(),
             pgn: String::new(),
+            rules,
         }
     }
 
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
     /// Undoes the last move that was played
     pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
-        if self.pgn.len() == 0 {
+        if self.pgn.is_empty() {
             return Err(NoMoveToUndoError);
         }
+        if self.rules == Rules::Infinite {
+            // the last move may have removed a mark, so the board is rebuilt from the moves before it
+            self.pgn.truncate(self.pgn.len() - 1);
+            self.bitboard = self.infinite_state().marks();
+            return Ok(());
+        }
         self.bitboard.clear_square(
             self.pgn
                 .chars()
@@ -99,24 +126,40 @@ impl Board {
         Ok(())
     }
 
-    /// Returns:
-    /// * 1 if X has won
-    /// * -1 if O has won
-    /// * 0 if the game is ongoing or is a draw
-    pub fn situation(&self) -> i8 {
-        if self.bitboard.x_won() {
-            return 1;
+    /// Returns whether X or O has won, the game is drawn or it is still ongoing.
+    /// Under misère rules the player who completes a line loses,
+    /// and under `Rules::Infinite` the game is drawn once a position comes up for the third time.
+    pub fn situation(&self) -> Outcome {
+        let outcome = self.rules.outcome(&self.bitboard);
+        if self.rules == Rules::Infinite
+            && outcome == Outcome::Ongoing
+            && self.repetitions() >= REPETITIONS_FOR_DRAW
+        {
+            return Outcome::Draw;
         }
-        if self.bitboard.o_won() {
-            return -1;
+        outcome
+    }
+
+    // the position as `Rules::Infinite` sees it, which includes the order the marks were placed in
+    fn infinite_state(&self) -> InfiniteState {
+        InfiniteState::from_pgn(&self.pgn)
+    }
+
+    // the number of times the current position has come up in the game, counting this one
+    fn repetitions(&self) -> usize {
+        let current = self.infinite_state();
+        let mut state = InfiniteState::new();
+        let mut count = usize::from(state == current);
+        for square in self.pgn.chars().filter_map(|c| c.to_digit(10)) {
+            state = state.play(square as u8);
+            count += usize::from(state == current);
         }
-        0
+        count
     }
 
     /// Returns a bool indication whether or not a certain move is possible in the position
-    ///
     pub fn is_valid_move(&self, square: i8) -> bool {
-        square >= 0 && square < 9 && self.bitboard.is_legal(square as u8) && self.is_in_play()
+        (0..9).contains(&square) && self.bitboard.is_legal(square as u8) && self.is_in_play()
     }
 
     /// Tests if a PGN is valid
@@ -125,10 +168,11 @@ impl Board {
     /// To save a certain position or game, you can use the PGN or Portable Game Notation format.
     /// A PGN is a simple concatenation of moves notated in the above manner,
     /// such that "042" yields the following position:
-    ///
-    ///     X  .  X
-    ///     .  O  .
-    ///     .  .  .
+    /// ```text
+    /// X  .  X
+    /// .  O  .
+    /// .  .  .
+    /// ```
     ///``` ignore
     /// use crate::norts::board::Board;
     ///
@@ -159,10 +203,11 @@ impl Board {
     /// To save a certain position or game, you can use the PGN or Portable Game Notation format.
     /// A PGN is a simple concatenation of moves notated in the above manner,
     /// such that "042" yields the following position:
-    ///
-    ///     X  .  X
-    ///     .  O  .
-    ///     .  .  .
+    /// ```text
+    /// X  .  X
+    /// .  O  .
+    /// .  .  .
+    /// ```
     ///``` ignore
     /// use crate::norts::board::Board;
     ///
@@ -172,31 +217,29 @@ impl Board {
     /// }
     /// ```
     pub fn parse_pgn(pgn: &str) -> Result<Board, InvalidPgnError> {
-        if Board::is_valid_pgn(pgn) {
-            let mut pos = Board::new();
-            for c in pgn.chars() {
-                if c.is_numeric() {
-                    if (c.to_string().parse::<i8>().unwrap()) < 9 {
-                        pos.play(c.to_string().parse::<i8>().unwrap()).unwrap();
-                    }
-                } else {
-                    ()
-                }
-            }
-            Ok(pos)
-        } else {
-            Err(InvalidPgnError)
+        Board::parse_pgn_with_rules(pgn, Rules::Standard)
+    }
+
+    /// Same as `Board::parse_pgn` but the game is played with the given rules.
+    /// Under `Rules::Infinite` games can be longer than nine moves and squares can be played more than once.
+    pub fn parse_pgn_with_rules(pgn: &str, rules: Rules) -> Result<Board, InvalidPgnError> {
+        let mut pos = Board::with_rules(rules);
+        for c in pgn.chars() {
+            let square = c.to_digit(10).ok_or(InvalidPgnError)?;
+            pos.play(square as i8).map_err(|_| InvalidPgnError)?;
         }
+        Ok(pos)
     }
 
     /// Plays a move to the certain square.
     ///
     /// ## Move Notation
     /// Every square is applied a number by this grid:
-    ///
-    ///     0  1  2
-    ///     3  4  5
-    ///     6  7  8
+    /// ```text
+    /// 0  1  2
+    /// 3  4  5
+    /// 6  7  8
+    /// ```
     /// A move is notated by the square on which a piece is placed, X goes first.
     /// The following example creates a board and plays some moves:
     /// ``` ignore
@@ -213,7 +256,11 @@ impl Board {
     ///  ```
     pub fn play(&mut self, square: i8) -> Result<bool, IllegalMoveError> {
         if self.is_valid_move(square) {
-            self.bitboard.play(square as u8);
+            if self.rules == Rules::Infinite {
+                self.bitboard = self.infinite_state().play(square as u8).marks();
+            } else {
+                self.bitboard.play(square as u8);
+            }
             self.pgn += &*square.to_string();
             Ok(true)
         } else {
@@ -223,40 +270,247 @@ impl Board {
 
     /// Returns a bool indicating whether or not the game has ended.
     pub fn is_in_play(&self) -> bool {
-        !(self.bitboard.x_won() || self.bitboard.o_won() || self.bitboard.is_draw())
+        self.situation() == Outcome::Ongoing
     }
 
     /// Returns the best move in the position.
-    pub fn best_move(&mut self) -> Result<i8, PositionAlreadyConcludedError> {
+    /// Under standard rules this is looked up in the tablebase, which is generated the first time it is needed.
+    /// Misère games and positions that can't be reached in a legal game are searched instead,
+    /// and `Rules::Infinite` games are looked up in their own table, solved by retrograde analysis.
+    pub fn best_move(&mut self) -> Result<Square, PositionAlreadyConcludedError> {
         if self.is_in_play() {
-            Ok(search(&mut self.bitboard, i8::MIN, i8::MAX).1 as i8)
+            let entry = match self.rules {
+                Rules::Standard => Tablebase::global().probe(&self.bitboard),
+                Rules::Misere => None,
+                Rules::Infinite => {
+                    let square = infinite::best_moves(&self.infinite_state()).1[0];
+                    return Ok(Square::new(square).unwrap());
+                }
+            };
+            match entry {
+                Some(entry) => Ok(entry.best_move().unwrap()),
+                None => {
+                    let best_move = search(&mut self.bitboard, i8::MIN, i8::MAX, self.rules).1;
+                    Ok(Square::new(best_move).unwrap())
+                }
+            }
         } else {
             Err(PositionAlreadyConcludedError)
         }
     }
 
-    pub fn current_player(&self) -> i8 {
-        // converts bool to 1 / -1
-        -((self.bitboard.current_player() as i8 * -2) + 1)
+    /// Returns the best move in the position using the given searcher,
+    /// so that its transposition table is reused between calls.
+    /// The searcher is switched to the board's rules, see `Searcher::set_rules`.
+    /// It isn't used under `Rules::Infinite`, see `Board::best_move`.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut Searcher,
+    ) -> Result<Square, PositionAlreadyConcludedError> {
+        if self.rules == Rules::Infinite {
+            return self.best_move();
+        }
+        searcher.switch_rules(self.rules);
+        if self.is_in_play() {
+            Ok(searcher.search(&mut self.bitboard).1.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut out = String::new();
-        for row in 0..3 {
-            for col in 0..3 {
-                if (self.bitboard.x_bitboard & (1 << ((row * 3) + col))) == 1 << ((row * 3) + col) {
-                    out += " X "
-                } else if (self.bitboard.o_bitboard & (1 << ((row * 3) + col)))
-                    == 1 << ((row * 3) + col)
-                {
-                    out += " O "
-                } else {
-                    out += " . "
-                }
+    /// Returns the principal variation, the best moves for both sides until the end of the game,
+    /// in PGN notation.
+    /// Appending it to the board's PGN gives the whole game, which can be loaded with `Board::parse_pgn`.
+    /// ``` ignore
+    /// use norts::Board;
+    ///
+    /// fn main() {
+    ///     let mut pos = Board::parse_pgn("01").unwrap();
+    ///     let pv = pos.principal_variation().unwrap();
+    ///     let end = Board::parse_pgn(&(pos.pgn.clone() + &pv)).unwrap();
+    ///     end.show();
+    /// }
+    /// ```
+    pub fn principal_variation(&mut self) -> Result<String, PositionAlreadyConcludedError> {
+        self.principal_variation_with(&mut Searcher::with_table())
+    }
+
+    /// Same as `Board::principal_variation` but uses the given searcher,
+    /// so that its transposition table is reused between calls.
+    pub fn principal_variation_with(
+        &mut self,
+        searcher: &mut Searcher,
+    ) -> Result<String, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        if self.rules == Rules::Infinite {
+            // drawn games go round in circles until the third repetition
+            let mut pos = Board::parse_pgn_with_rules(&self.pgn, self.rules).unwrap();
+            let mut pv = String::new();
+            while let Ok(square) = pos.best_move() {
+                pos.play(square.into()).unwrap();
+                pv += &square.to_string();
             }
-            out += "\n"
+            return Ok(pv);
+        }
+        searcher.switch_rules(self.rules);
+        Ok(searcher
+            .principal_variation(&mut self.bitboard)
+            .iter()
+            .map(|square| square.to_string())
+            .collect())
+    }
+
+    /// Returns the evaluation of the position along with every move that achieves it,
+    /// in square order.
+    /// ``` ignore
+    /// use norts::{Board, Evaluation};
+    ///
+    /// fn main() {
+    ///     let mut pos = Board::parse_pgn("01").unwrap();
+    ///     let (eval, moves) = pos.best_moves().unwrap();
+    ///     assert_eq!(eval, Evaluation::XWinsIn(5));
+    ///     assert_eq!(moves.len(), 3);
+    /// }
+    /// ```
+    pub fn best_moves(
+        &mut self,
+    ) -> Result<(Evaluation, Vec<Square>), PositionAlreadyConcludedError> {
+        if self.rules == Rules::Infinite {
+            return self.infinite_best_moves();
+        }
+        if self.is_in_play() {
+            let evals = search_moves(&mut self.bitboard, self.rules);
+            Ok(best_of(&self.bitboard, evals))
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Same as `Board::best_moves` but uses the given searcher,
+    /// so that its transposition table is reused between calls.
+    pub fn best_moves_with(
+        &mut self,
+        searcher: &mut Searcher,
+    ) -> Result<(Evaluation, Vec<Square>), PositionAlreadyConcludedError> {
+        if self.rules == Rules::Infinite {
+            return self.infinite_best_moves();
+        }
+        searcher.switch_rules(self.rules);
+        if self.is_in_play() {
+            let evals = searcher.score_moves(&mut self.bitboard);
+            Ok(best_of(&self.bitboard, evals))
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    fn infinite_best_moves(
+        &self,
+    ) -> Result<(Evaluation, Vec<Square>), PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let (value, moves) = infinite::best_moves(&self.infinite_state());
+        let x_to_move = self.current_player() == 1;
+        Ok((
+            value.to_evaluation(x_to_move),
+            moves
+                .into_iter()
+                .map(|square| Square::new(square).unwrap())
+                .collect(),
+        ))
+    }
+
+    /// Returns the exact outcome of every square for the player to move, indexed by square,
+    /// with `None` for squares that are already taken.
+    /// Useful for showing how good each move is rather than just the best one.
+    /// ``` ignore
+    /// use norts::{Board, MoveOutcome};
+    ///
+    /// fn main() {
+    ///     let mut pos = Board::parse_pgn("01").unwrap();
+    ///     let analysis = pos.analyse().unwrap();
+    ///     assert_eq!(analysis[4].unwrap().outcome, MoveOutcome::Win);
+    ///     assert_eq!(analysis[1], None);
+    /// }
+    /// ```
+    pub fn analyse(&mut self) -> Result<[Option<MoveAnalysis>; 9], PositionAlreadyConcludedError> {
+        self.analyse_with(&mut Searcher::new())
+    }
+
+    /// Same as `Board::analyse` but uses the given searcher,
+    /// so that its transposition table is reused between calls.
+    pub fn analyse_with(
+        &mut self,
+        searcher: &mut Searcher,
+    ) -> Result<[Option<MoveAnalysis>; 9], PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        if self.rules == Rules::Infinite {
+            let mut analysis = [None; 9];
+            for (square, value) in infinite::move_values(&self.infinite_state()) {
+                let (outcome, moves_to_end) = match value {
+                    Value::Win(n) => (MoveOutcome::Win, n),
+                    Value::Loss(n) => (MoveOutcome::Loss, n),
+                    Value::Draw => (MoveOutcome::Draw, 0),
+                };
+                analysis[square as usize] = Some(MoveAnalysis {
+                    outcome,
+                    moves_to_end: moves_to_end.min(u8::MAX as u16) as u8,
+                });
+            }
+            return Ok(analysis);
+        }
+        searcher.switch_rules(self.rules);
+        let num_moves = self.bitboard.num_moves();
+        let x_to_move = self.bitboard.current_player();
+        let mut analysis = [None; 9];
+        for (square, eval) in searcher.score_moves(&mut self.bitboard) {
+            analysis[square as usize] = Some(MoveAnalysis::from_eval(eval, num_moves, x_to_move));
+        }
+        Ok(analysis)
+    }
+
+    /// Returns a move chosen by the engine playing at the given strength.
+    /// Any random choices are taken from `rng`, so the same seed always gives the same moves.
+    /// ``` ignore
+    /// use norts::{Board, Rng, Strength};
+    ///
+    /// fn main() {
+    ///     let mut rng = Rng::new(42);
+    ///     let mut pos = Board::new();
+    ///     let square = pos.engine_move(Strength::DepthLimited(2), &mut rng).unwrap();
+    ///     pos.play(square.into()).unwrap();
+    /// }
+    /// ```
+    pub fn engine_move(
+        &mut self,
+        strength: Strength,
+        rng: &mut Rng,
+    ) -> Result<Square, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let square = if self.rules == Rules::Infinite {
+            infinite::choose_move(&self.infinite_state(), strength, rng)
+        } else {
+            choose_move(&mut self.bitboard, strength, self.rules, rng)
+        }
+        .unwrap();
+        Ok(Square::new(square).unwrap())
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        // taken from the number of moves rather than the marks, which can vanish under `Rules::Infinite`
+        if self.pgn.len() % 2 == 0 {
+            1
+        } else {
+            -1
         }
-        out
     }
 
     /// Displays a visual representation of the board to the standard output.
@@ -285,3 +539,148 @@ impl Board {
         println!("*-----------------------*");
     }
 }
+
+// picks out the moves tied for the best eval for the player to move
+fn best_of(pos: &Bitboard, evals: Vec<(u8, i8)>) -> (Evaluation, Vec<Square>) {
+    let best = if pos.current_player() {
+        evals.iter().map(|(_, eval)| *eval).max()
+    } else {
+        evals.iter().map(|(_, eval)| *eval).min()
+    }
+    .unwrap();
+    let moves = evals
+        .into_iter()
+        .filter(|(_, eval)| *eval == best)
+        .map(|(square, _)| Square::new(square).unwrap())
+        .collect();
+    (Evaluation::from_score(best, pos.num_moves()), moves)
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..3 {
+            for col in 0..3 {
+                if (self.bitboard.x_bitboard & (1 << ((row * 3) + col))) == 1 << ((row * 3) + col) {
+                    write!(f, " X ")?;
+                } else if (self.bitboard.o_bitboard & (1 << ((row * 3) + col)))
+                    == 1 << ((row * 3) + col)
+                {
+                    write!(f, " O ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(indices: &[u8]) -> Vec<Square> {
+        indices.iter().map(|i| Square::new(*i).unwrap()).collect()
+    }
+
+    #[test]
+    fn best_moves() {
+        let (eval, moves) = Board::new().best_moves().unwrap();
+        assert_eq!(eval, Evaluation::Draw);
+        assert_eq!(moves, squares(&[0, 1, 2, 3, 4, 5, 6, 7, 8]));
+        let mut pos = Board::parse_pgn("01").unwrap();
+        let expected = (Evaluation::XWinsIn(5), squares(&[3, 4, 6]));
+        assert_eq!(pos.best_moves().unwrap(), expected);
+        assert_eq!(
+            pos.best_moves_with(&mut Searcher::with_table()).unwrap(),
+            expected
+        );
+        // X has won along the top row
+        assert!(Board::parse_pgn("03142").unwrap().best_moves().is_err());
+    }
+
+    #[test]
+    fn analyse() {
+        let draw = |moves_to_end| {
+            Some(MoveAnalysis {
+                outcome: MoveOutcome::Draw,
+                moves_to_end,
+            })
+        };
+        assert_eq!(Board::new().analyse().unwrap(), [draw(9); 9]);
+        let win = Some(MoveAnalysis {
+            outcome: MoveOutcome::Win,
+            moves_to_end: 5,
+        });
+        let analysis = Board::parse_pgn("01").unwrap().analyse().unwrap();
+        assert_eq!(
+            analysis,
+            [
+                None,
+                None,
+                draw(7),
+                win,
+                win,
+                draw(7),
+                win,
+                draw(7),
+                draw(7)
+            ]
+        );
+        assert!(Board::parse_pgn("03142").unwrap().analyse().is_err());
+    }
+
+    #[test]
+    fn principal_variation() {
+        assert_eq!(
+            Board::parse_pgn("01")
+                .unwrap()
+                .principal_variation()
+                .unwrap(),
+            "36425"
+        );
+        assert!(Board::parse_pgn("03142")
+            .unwrap()
+            .principal_variation()
+            .is_err());
+    }
+
+    #[test]
+    fn principal_variations_end_with_the_evaluation() {
+        let mut searcher = Searcher::with_table();
+        // every position reached in a game still in play, each under the first move order found
+        let mut games = vec![String::new()];
+        let mut seen = std::collections::HashSet::from([Bitboard::new()]);
+        while let Some(pgn) = games.pop() {
+            let mut pos = Board::parse_pgn(&pgn).unwrap();
+            let (eval, _) = pos.best_moves().unwrap();
+            let pv = pos.principal_variation_with(&mut searcher).unwrap();
+            let mut end = pos.clone();
+            for square in pv.chars() {
+                assert!(end.is_in_play());
+                assert!(end.play(square.to_digit(10).unwrap() as i8).is_ok());
+            }
+            assert_eq!(end.situation(), eval.outcome());
+            let length = match eval {
+                Evaluation::XWinsIn(n) | Evaluation::OWinsIn(n) => n as usize,
+                Evaluation::Draw => 9 - pgn.len(),
+            };
+            assert_eq!(pv.len(), length);
+            for square in 0..9 {
+                let mut child = pos.clone();
+                if child.play(square).is_ok() && child.is_in_play() && seen.insert(child.bitboard) {
+                    games.push(child.pgn);
+                }
+            }
+        }
+        // every legal position except the 958 finished ones
+        assert_eq!(seen.len(), 5478 - 958);
+    }
+}
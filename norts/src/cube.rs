@@ -0,0 +1,415 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::cube_search::CubeSearcher;
+use crate::evaluation::Outcome;
+use crate::mnk::InvalidGeometryError;
+
+/// The winning lines of a cube, precomputed as bitboard masks.
+#[derive(Debug)]
+struct CubeLines {
+    lines: Vec<u64>,
+    lines_through: Vec<Vec<usize>>,
+    // the 48 rotations and reflections of the cube, each as a map from cell to cell
+    symmetries: Vec<Vec<usize>>,
+}
+
+impl CubeLines {
+    fn new(size: usize) -> CubeLines {
+        let n = size as isize;
+        let cells = size * size * size;
+        let mut lines = Vec::new();
+        let mut lines_through = vec![Vec::new(); cells];
+        let in_bounds = |c: isize| (0..n).contains(&c);
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    for dz in -1..=1isize {
+                        for dy in -1..=1isize {
+                            for dx in -1..=1isize {
+                                // only take one of each pair of opposite directions
+                                if (dz, dy, dx) <= (0, 0, 0) {
+                                    continue;
+                                }
+                                // lines must start on the edge of the cube and cross it completely
+                                if in_bounds(x - dx) && in_bounds(y - dy) && in_bounds(z - dz) {
+                                    continue;
+                                }
+                                let (ex, ey, ez) =
+                                    (x + dx * (n - 1), y + dy * (n - 1), z + dz * (n - 1));
+                                if !(in_bounds(ex) && in_bounds(ey) && in_bounds(ez)) {
+                                    continue;
+                                }
+                                let mut line = 0u64;
+                                for i in 0..n {
+                                    let cell =
+                                        ((z + dz * i) * n * n + (y + dy * i) * n + x + dx * i)
+                                            as usize;
+                                    line |= 1 << cell;
+                                    lines_through[cell].push(lines.len());
+                                }
+                                lines.push(line);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        CubeLines {
+            lines,
+            lines_through,
+            symmetries: symmetries(size),
+        }
+    }
+}
+
+fn symmetries(size: usize) -> Vec<Vec<usize>> {
+    const AXIS_ORDERS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    let mut maps = Vec::with_capacity(48);
+    for order in AXIS_ORDERS {
+        for flips in 0..8 {
+            let map = (0..size * size * size)
+                .map(|cell| {
+                    let coords = [cell / (size * size), cell / size % size, cell % size];
+                    let mut image = [0; 3];
+                    for (axis, coord) in image.iter_mut().enumerate() {
+                        *coord = coords[order[axis]];
+                        if flips & (1 << axis) != 0 {
+                            *coord = size - 1 - *coord;
+                        }
+                    }
+                    image[0] * size * size + image[1] * size + image[2]
+                })
+                .collect();
+            maps.push(map);
+        }
+    }
+    maps
+}
+
+/// 3D tic-tac-toe on a cube of 2 to 4 cells a side, 3x3x3 or 4x4x4 (Qubic) being the usual sizes.
+/// A line is a row of `size` cells in any of the 13 directions, including those through
+/// the layers and the 4 space diagonals, which gives 49 lines for 3x3x3 and 76 for 4x4x4.
+///
+/// Cells are numbered `layer * size * size + row * size + col`.
+/// ``` ignore
+/// use norts::CubeBoard;
+///
+/// fn main() {
+///     let mut pos = CubeBoard::new(4).unwrap();
+///     pos.play(0).unwrap();
+///     pos.show();
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CubeBoard {
+    size: usize,
+    lines: Arc<CubeLines>,
+    pub x_bitboard: u64,
+    pub o_bitboard: u64,
+    history: Vec<usize>,
+}
+
+impl CubeBoard {
+    pub fn new(size: usize) -> Result<CubeBoard, InvalidGeometryError> {
+        if !(2..=4).contains(&size) {
+            return Err(InvalidGeometryError);
+        }
+        Ok(CubeBoard {
+            size,
+            lines: Arc::new(CubeLines::new(size)),
+            x_bitboard: 0,
+            o_bitboard: 0,
+            history: Vec::new(),
+        })
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// a list of cells separated by commas such as "13,0,26".
+    pub fn parse_pgn(size: usize, pgn: &str) -> Result<CubeBoard, InvalidPgnError> {
+        let mut pos = CubeBoard::new(size).map_err(|_| InvalidPgnError)?;
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let cell = part.parse().map_err(|_| InvalidPgnError)?;
+            pos.play(cell).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `CubeBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|cell| cell.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.size * self.size * self.size
+    }
+
+    /// Every winning line as a mask of cells.
+    pub fn lines(&self) -> &[u64] {
+        &self.lines.lines
+    }
+
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.x_to_move() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn x_to_move(&self) -> bool {
+        self.history.len() % 2 == 0
+    }
+
+    #[inline(always)]
+    pub(crate) fn empty(&self) -> u64 {
+        let all = if self.num_cells() == 64 {
+            u64::MAX
+        } else {
+            (1 << self.num_cells()) - 1
+        };
+        all & !(self.x_bitboard | self.o_bitboard)
+    }
+
+    pub fn is_valid_move(&self, cell: usize) -> bool {
+        cell < self.num_cells() && self.empty() & (1 << cell) != 0 && self.is_in_play()
+    }
+
+    /// Returns every legal move in cell order.
+    pub fn legal_moves(&self) -> Vec<usize> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        bits(self.empty()).collect()
+    }
+
+    pub fn play(&mut self, cell: usize) -> Result<bool, IllegalMoveError> {
+        if self.is_valid_move(cell) {
+            self.play_unchecked(cell);
+            Ok(true)
+        } else {
+            Err(IllegalMoveError)
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        if self.history.is_empty() {
+            return Err(NoMoveToUndoError);
+        }
+        self.undo_unchecked();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn play_unchecked(&mut self, cell: usize) {
+        if self.x_to_move() {
+            self.x_bitboard |= 1 << cell;
+        } else {
+            self.o_bitboard |= 1 << cell;
+        }
+        self.history.push(cell);
+    }
+
+    #[inline(always)]
+    pub(crate) fn undo_unchecked(&mut self) {
+        let cell = self.history.pop().unwrap();
+        self.x_bitboard &= !(1 << cell);
+        self.o_bitboard &= !(1 << cell);
+    }
+
+    /// Returns true if the last move completed a line.
+    #[inline(always)]
+    pub(crate) fn last_move_won(&self) -> bool {
+        let cell = match self.history.last() {
+            Some(cell) => *cell,
+            None => return false,
+        };
+        let marks = if self.x_bitboard & (1 << cell) != 0 {
+            self.x_bitboard
+        } else {
+            self.o_bitboard
+        };
+        self.lines.lines_through[cell]
+            .iter()
+            .any(|line| marks & self.lines.lines[*line] == self.lines.lines[*line])
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        self.history.len() == self.num_cells()
+    }
+
+    /// Indices into `CubeBoard::lines` of the lines passing through `cell`.
+    pub fn lines_through(&self, cell: usize) -> &[usize] {
+        &self.lines.lines_through[cell]
+    }
+
+    /// The smallest of the position's images under the 48 symmetries of the cube,
+    /// equal for any two positions which are rotations or reflections of each other.
+    pub fn canonical(&self) -> (u64, u64) {
+        let map_bits =
+            |mask: u64, map: &[usize]| bits(mask).fold(0u64, |acc, cell| acc | 1 << map[cell]);
+        self.lines
+            .symmetries
+            .iter()
+            .map(|map| {
+                (
+                    map_bits(self.x_bitboard, map),
+                    map_bits(self.o_bitboard, map),
+                )
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// Returns the empty cells which would complete a line for X, or for O if `x` is false.
+    pub fn threats(&self, x: bool) -> u64 {
+        let (own, other) = if x {
+            (self.x_bitboard, self.o_bitboard)
+        } else {
+            (self.o_bitboard, self.x_bitboard)
+        };
+        let mut threats = 0;
+        for line in &self.lines.lines {
+            if other & line == 0 && (own & line).count_ones() as usize == self.size - 1 {
+                threats |= line & !own;
+            }
+        }
+        threats
+    }
+
+    /// Returns whether X or O has won, the game is drawn or it is still ongoing.
+    pub fn situation(&self) -> Outcome {
+        if self.last_move_won() {
+            if self.x_to_move() {
+                Outcome::OWon
+            } else {
+                Outcome::XWon
+            }
+        } else if self.is_full() {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        !(self.last_move_won() || self.is_full())
+    }
+
+    /// Returns the best move found by a default `CubeSearcher`,
+    /// which solves 3x3x3 and uses a depth limited search on 4x4x4.
+    pub fn best_move(&mut self) -> Result<usize, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut CubeSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut CubeSearcher,
+    ) -> Result<usize, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            Ok(searcher.search(self).best_move.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output, one layer after another.
+    pub fn show(&self) {
+        println!("*-----------------------*");
+        print!("{}", self);
+        println!("*-----------------------*");
+    }
+}
+
+impl fmt::Display for CubeBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for layer in 0..self.size {
+            writeln!(f, " Layer {}:", layer)?;
+            for row in 0..self.size {
+                for col in 0..self.size {
+                    let cell = (layer * self.size + row) * self.size + col;
+                    if self.x_bitboard & (1 << cell) != 0 {
+                        write!(f, " X ")?;
+                    } else if self.o_bitboard & (1 << cell) != 0 {
+                        write!(f, " O ")?;
+                    } else {
+                        write!(f, " . ")?;
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterates over the set bits of a mask in ascending order.
+pub(crate) fn bits(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
+        }
+        let bit = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
+        Some(bit)
+    })
+}
@@ -0,0 +1,397 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashSet;
+
+use crate::cube::{bits, CubeBoard};
+use crate::evaluation::Evaluation;
+use crate::iterative::{to_evaluation, IterativeSearch, Node, SearchPosition, WIN};
+
+// proof and disproof numbers of a solved node
+const PN_INFINITY: u32 = u32::MAX;
+// symmetric positions are rare after the first few moves, so they are only merged up to here
+const SYMMETRY_DEPTH: usize = 4;
+
+/// Nodes searched by a default `CubeSearcher` before it settles for its last completed depth.
+pub const CUBE_NODE_LIMIT: u64 = 1_000_000;
+
+/// The result of searching a `CubeBoard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CubeSearchResult {
+    pub best_move: Option<usize>,
+    /// Score from the point of view of the player to move, wins are ±1,000,000,000 less the length of the game.
+    pub score: i32,
+    /// The exact value of the position if the search was able to solve it.
+    pub evaluation: Option<Evaluation>,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search for `CubeBoard`s with a transposition table.
+/// Immediate wins are always taken and single threats always blocked without counting towards the depth,
+/// which is enough to solve 3x3x3 almost instantly.
+pub struct CubeSearcher {
+    search: IterativeSearch<(u64, u64), usize>,
+}
+
+impl CubeSearcher {
+    /// Returns a searcher with no depth limit and a budget of `CUBE_NODE_LIMIT` nodes per search.
+    pub fn new() -> CubeSearcher {
+        CubeSearcher {
+            search: IterativeSearch::new(CUBE_NODE_LIMIT),
+        }
+    }
+
+    /// Limits how many moves ahead the search looks.
+    pub fn with_max_depth(mut self, depth: u8) -> CubeSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit.
+    pub fn with_node_limit(mut self, nodes: u64) -> CubeSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+
+    /// Searches the position, see `CubeSearcher`.
+    pub fn search(&mut self, pos: &mut CubeBoard) -> CubeSearchResult {
+        self.search.reset();
+        if !pos.is_in_play() {
+            return CubeSearchResult {
+                best_move: None,
+                score: 0,
+                evaluation: None,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+        let iteration = self.search.search(pos, pos.num_cells() - pos.num_moves());
+        let x_score = if pos.x_to_move() {
+            iteration.score
+        } else {
+            -iteration.score
+        };
+        CubeSearchResult {
+            best_move: iteration.best_move,
+            score: iteration.score,
+            evaluation: iteration
+                .solved
+                .then(|| to_evaluation(x_score, pos.num_moves())),
+            depth: iteration.depth,
+            nodes: self.search.nodes(),
+        }
+    }
+}
+
+impl Default for CubeSearcher {
+    fn default() -> Self {
+        CubeSearcher::new()
+    }
+}
+
+impl SearchPosition for CubeBoard {
+    type Move = usize;
+    type Key = (u64, u64);
+
+    fn key(&self) -> Self::Key {
+        (self.x_bitboard, self.o_bitboard)
+    }
+
+    fn mover(&self) -> bool {
+        self.x_to_move()
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<usize> {
+        if self.last_move_won() {
+            return Node::Solved(-(WIN - self.num_moves() as i32));
+        }
+        if self.is_full() {
+            return Node::Solved(0);
+        }
+        let empty = self.empty();
+        let num_moves = self.num_moves() as i32;
+        if self.threats(self.x_to_move()) & empty != 0 {
+            return Node::Solved(WIN - (num_moves + 1));
+        }
+        let blocks = self.threats(!self.x_to_move()) & empty;
+        if blocks.count_ones() > 1 {
+            // only one threat can be blocked
+            Node::Solved(-(WIN - (num_moves + 2)))
+        } else if blocks != 0 {
+            Node::Forced(vec![blocks.trailing_zeros() as usize])
+        } else if depth == 0 {
+            Node::Horizon(heuristic(self))
+        } else {
+            Node::Search
+        }
+    }
+
+    // winning moves if there are any, then forced blocks, then every cell with the best connected first
+    fn ordered_moves(&mut self) -> (Vec<usize>, bool) {
+        let moves = forced_moves(self).unwrap_or_else(|| {
+            let mut moves: Vec<usize> = bits(self.empty()).collect();
+            moves.sort_by_key(|cell| std::cmp::Reverse(self.lines_through(*cell).len()));
+            moves
+        });
+        (moves, true)
+    }
+
+    fn play(&mut self, cell: usize) {
+        self.play_unchecked(cell);
+    }
+
+    fn undo(&mut self) {
+        self.undo_unchecked();
+    }
+}
+
+// the only sensible moves when the player to move can win or has to block
+fn forced_moves(pos: &CubeBoard) -> Option<Vec<usize>> {
+    let empty = pos.empty();
+    let wins = pos.threats(pos.x_to_move()) & empty;
+    if wins != 0 {
+        return Some(vec![wins.trailing_zeros() as usize]);
+    }
+    let blocks = pos.threats(!pos.x_to_move()) & empty;
+    if blocks != 0 {
+        return Some(bits(blocks).collect());
+    }
+    None
+}
+
+// counts the lines each player can still complete, weighting fuller lines more heavily
+fn heuristic(pos: &CubeBoard) -> i32 {
+    let mut score = 0;
+    for line in pos.lines() {
+        let x = (pos.x_bitboard & line).count_ones();
+        let o = (pos.o_bitboard & line).count_ones();
+        if o == 0 && x > 0 {
+            score += 1 << (2 * x);
+        } else if x == 0 && o > 0 {
+            score -= 1 << (2 * o);
+        }
+    }
+    if pos.x_to_move() {
+        score
+    } else {
+        -score
+    }
+}
+
+/// What a proof-number search found out about the player to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Proof {
+    /// The player to move can force a win.
+    Win,
+    /// The player to move cannot force a win, the game is a draw or a loss.
+    NoWin,
+    /// The node budget ran out first.
+    Unknown,
+}
+
+/// The result of `proof_number_search`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofNumberResult {
+    pub proof: Proof,
+    /// A winning move, when the proof succeeded.
+    pub winning_move: Option<usize>,
+    /// Number of nodes in the proof tree.
+    pub nodes: usize,
+}
+
+struct PnNode {
+    parent: usize,
+    cell: usize,
+    proof: u32,
+    disproof: u32,
+    children: Vec<usize>,
+}
+
+/// Tries to prove that the player to move can force a win, using proof-number search.
+/// The most-proving node, the one whose result would do the most to settle the root, is expanded each step,
+/// so the search concentrates on forcing lines rather than searching every move to the same depth.
+/// Gives up with `Proof::Unknown` once the tree holds `node_limit` nodes.
+/// 3x3x3 is proved a first player win in well under a million nodes,
+/// Qubic from the empty board is far beyond any sensible budget.
+/// ``` ignore
+/// use norts::{proof_number_search, CubeBoard, Proof};
+///
+/// fn main() {
+///     let pos = CubeBoard::new(3).unwrap();
+///     let result = proof_number_search(&pos, 1_000_000);
+///     assert_eq!(result.proof, Proof::Win);
+///     assert_eq!(result.winning_move, Some(13));
+/// }
+/// ```
+pub fn proof_number_search(pos: &CubeBoard, node_limit: usize) -> ProofNumberResult {
+    let mut pos = pos.clone();
+    let root_x = pos.x_to_move();
+    let mut nodes = vec![PnNode {
+        parent: 0,
+        cell: 0,
+        proof: 1,
+        disproof: 1,
+        children: Vec::new(),
+    }];
+    if !pos.is_in_play() {
+        return ProofNumberResult {
+            proof: Proof::NoWin,
+            winning_move: None,
+            nodes: 1,
+        };
+    }
+    while nodes[0].proof != 0 && nodes[0].disproof != 0 && nodes.len() < node_limit {
+        // walk down to the most-proving node, playing the moves on the way
+        let mut current = 0;
+        while !nodes[current].children.is_empty() {
+            let or_node = pos.x_to_move() == root_x;
+            current = *nodes[current]
+                .children
+                .iter()
+                .min_by_key(|child| {
+                    if or_node {
+                        nodes[**child].proof
+                    } else {
+                        nodes[**child].disproof
+                    }
+                })
+                .unwrap();
+            pos.play_unchecked(nodes[current].cell);
+        }
+
+        // expand it
+        let moves = forced_moves(&pos).unwrap_or_else(|| bits(pos.empty()).collect());
+        let mut seen = HashSet::new();
+        for cell in moves {
+            pos.play_unchecked(cell);
+            // moves leading to rotations or reflections of each other need only be proved once
+            if pos.num_moves() <= SYMMETRY_DEPTH && !seen.insert(pos.canonical()) {
+                pos.undo_unchecked();
+                continue;
+            }
+            let (proof, disproof) = if pos.last_move_won() {
+                // whoever just moved won
+                if pos.x_to_move() != root_x {
+                    (0, PN_INFINITY)
+                } else {
+                    (PN_INFINITY, 0)
+                }
+            } else if pos.is_full() {
+                (PN_INFINITY, 0)
+            } else {
+                // a node with fewer replies is cheaper to settle for whoever has to refute every one of them
+                let replies =
+                    forced_moves(&pos).map_or(pos.empty().count_ones(), |moves| moves.len() as u32);
+                if pos.x_to_move() == root_x {
+                    (1, replies)
+                } else {
+                    (replies, 1)
+                }
+            };
+            pos.undo_unchecked();
+            nodes.push(PnNode {
+                parent: current,
+                cell,
+                proof,
+                disproof,
+                children: Vec::new(),
+            });
+            let child = nodes.len() - 1;
+            nodes[current].children.push(child);
+        }
+
+        // update the proof numbers back up to the root, undoing moves on the way
+        loop {
+            let or_node = pos.x_to_move() == root_x;
+            let children = &nodes[current].children;
+            let (proof, disproof) = if or_node {
+                (
+                    children.iter().map(|c| nodes[*c].proof).min().unwrap(),
+                    children
+                        .iter()
+                        .fold(0u32, |sum, c| sum.saturating_add(nodes[*c].disproof)),
+                )
+            } else {
+                (
+                    children
+                        .iter()
+                        .fold(0u32, |sum, c| sum.saturating_add(nodes[*c].proof)),
+                    children.iter().map(|c| nodes[*c].disproof).min().unwrap(),
+                )
+            };
+            nodes[current].proof = proof;
+            nodes[current].disproof = disproof;
+            if current == 0 {
+                break;
+            }
+            pos.undo_unchecked();
+            current = nodes[current].parent;
+        }
+    }
+    let proof = if nodes[0].proof == 0 {
+        Proof::Win
+    } else if nodes[0].disproof == 0 {
+        Proof::NoWin
+    } else {
+        Proof::Unknown
+    };
+    let winning_move = if proof == Proof::Win {
+        nodes[0]
+            .children
+            .iter()
+            .find(|child| nodes[**child].proof == 0)
+            .map(|child| nodes[*child].cell)
+    } else {
+        None
+    };
+    ProofNumberResult {
+        proof,
+        winning_move,
+        nodes: nodes.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_player_wins_3x3x3() {
+        let mut pos = CubeBoard::new(3).unwrap();
+        let result = CubeSearcher::new().search(&mut pos);
+        assert_eq!(result.evaluation, Some(Evaluation::XWinsIn(7)));
+        // the centre cell is on the most lines
+        assert_eq!(result.best_move, Some(13));
+
+        let proof = proof_number_search(&pos, 1_000_000);
+        assert_eq!(proof.proof, Proof::Win);
+        pos.play(proof.winning_move.unwrap()).unwrap();
+        let result = CubeSearcher::new().search(&mut pos);
+        assert_eq!(result.evaluation, Some(Evaluation::XWinsIn(6)));
+    }
+}
@@ -0,0 +1,130 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+
+/// The state of a game, see `Board::situation`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    XWon,
+    OWon,
+    Draw,
+    Ongoing,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::XWon => write!(f, "X won"),
+            Outcome::OWon => write!(f, "O won"),
+            Outcome::Draw => write!(f, "Draw"),
+            Outcome::Ongoing => write!(f, "Ongoing"),
+        }
+    }
+}
+
+/// The game-theoretic value of a position with perfect play from both sides.
+/// Wins carry the number of moves until the game ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evaluation {
+    XWinsIn(u8),
+    OWinsIn(u8),
+    Draw,
+}
+
+impl Evaluation {
+    /// Converts a raw search score into an evaluation of a position
+    /// in which `num_moves` moves have been played.
+    /// Scores are `100 - game length` for an X win and `-100 + game length` for an O win.
+    pub(crate) fn from_score(score: i8, num_moves: u8) -> Evaluation {
+        match score {
+            0 => Evaluation::Draw,
+            s if s > 0 => Evaluation::XWinsIn(100 - s as u8 - num_moves),
+            s => Evaluation::OWinsIn((100 + s) as u8 - num_moves),
+        }
+    }
+
+    /// Returns the outcome the game will end in.
+    pub fn outcome(&self) -> Outcome {
+        match self {
+            Evaluation::XWinsIn(_) => Outcome::XWon,
+            Evaluation::OWinsIn(_) => Outcome::OWon,
+            Evaluation::Draw => Outcome::Draw,
+        }
+    }
+}
+
+impl fmt::Display for Evaluation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Evaluation::XWinsIn(n) => write!(f, "X wins in {}", n),
+            Evaluation::OWinsIn(n) => write!(f, "O wins in {}", n),
+            Evaluation::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+/// A square on the board, numbered as described in `Board::play`.
+/// Only squares 0 to 8 can be constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    /// Returns the square with the given number, or `None` if it is off the board.
+    pub fn new(index: u8) -> Option<Square> {
+        if index < 9 {
+            Some(Square(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn row(self) -> u8 {
+        self.0 / 3
+    }
+
+    pub fn col(self) -> u8 {
+        self.0 % 3
+    }
+}
+
+impl From<Square> for i8 {
+    fn from(square: Square) -> i8 {
+        square.0 as i8
+    }
+}
+
+impl From<Square> for u8 {
+    fn from(square: Square) -> u8 {
+        square.0
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
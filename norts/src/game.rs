@@ -0,0 +1,220 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+
+use crate::board::{Board, IllegalMoveError};
+use crate::cube::CubeBoard;
+use crate::cube_search::CubeSearcher;
+use crate::evaluation::{Outcome, Square};
+use crate::gomoku::GomokuBoard;
+use crate::gomoku_search::GomokuSearcher;
+use crate::mnk::MnkBoard;
+use crate::mnk_search::MnkSearcher;
+use crate::morris::{MorrisBoard, MorrisMove};
+use crate::notakto::{NotaktoBoard, NotaktoMove};
+use crate::numerical::{NumericalBoard, NumericalMove};
+use crate::order_chaos::{OrderChaosBoard, OrderChaosMove};
+use crate::order_chaos_search::OrderChaosSearcher;
+use crate::quantum::{QuantumBoard, QuantumMove, QuantumSearcher};
+use crate::search::Searcher;
+use crate::ultimate::{UltimateBoard, UltimateMove};
+use crate::ultimate_search::UltimateSearcher;
+use crate::wild::{WildBoard, WildMove};
+
+/// A two player game, implemented by every board in the crate so that engines such as `Mcts` can play any of them.
+/// The methods match the boards' own, with the first player counted as X.
+pub trait Game: Clone {
+    type Move: Copy + PartialEq + fmt::Debug + fmt::Display;
+
+    /// Returns every legal move, which is empty once the game is over.
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Plays a move returned by `legal_moves`.
+    fn play_move(&mut self, mv: Self::Move);
+
+    /// Returns 1 if the first player is to move and -1 if the second player is to move.
+    fn current_player(&self) -> i8;
+
+    /// Returns `Outcome::XWon` if the first player won, `Outcome::OWon` if the second player won,
+    /// `Outcome::Draw` or `Outcome::Ongoing`.
+    fn situation(&self) -> Outcome;
+}
+
+/// Something which picks moves in a `Game`, such as `Mcts`, one of the searchers or a player from the `player` module,
+/// so that any two can be pitted against each other with `play_game`.
+pub trait Engine<G: Game> {
+    /// Returns the move to play, or `None` if the game is over.
+    fn choose_move(&mut self, game: &G) -> Option<G::Move>;
+
+    /// Called by `play_game` after the opponent moves, with the position after their move.
+    /// Does nothing unless the engine keeps track of the game itself.
+    fn opponent_moved(&mut self, _game: &G, _mv: G::Move) {}
+}
+
+/// Plays a game from `start` until it ends, `first` choosing moves for the first player and `second` for the other,
+/// and returns the final position. Each engine is told about the other's moves through `Engine::opponent_moved`.
+/// An engine choosing an illegal move, or no move before the game is over, stops the game with an `IllegalMoveError`.
+/// ``` ignore
+/// use norts::{play_game, Board, Searcher, Strength, StrengthPlayer};
+///
+/// fn main() {
+///     let mut random = StrengthPlayer::new(Strength::Random, 1);
+///     let end = play_game(&Board::new(), &mut random, &mut Searcher::new()).unwrap();
+///     end.show();
+/// }
+/// ```
+pub fn play_game<G: Game>(
+    start: &G,
+    first: &mut dyn Engine<G>,
+    second: &mut dyn Engine<G>,
+) -> Result<G, IllegalMoveError> {
+    match play_out(start, first, second) {
+        (game, true) => Ok(game),
+        (_, false) => Err(IllegalMoveError),
+    }
+}
+
+/// Plays the game out as in `play_game`, returning the last position and whether every move was legal.
+/// If one wasn't, the player to move in the returned position chose it.
+pub(crate) fn play_out<G: Game>(
+    start: &G,
+    first: &mut dyn Engine<G>,
+    second: &mut dyn Engine<G>,
+) -> (G, bool) {
+    let mut game = start.clone();
+    while game.situation() == Outcome::Ongoing {
+        let first_to_move = game.current_player() == 1;
+        let mv = if first_to_move {
+            first.choose_move(&game)
+        } else {
+            second.choose_move(&game)
+        };
+        let mv = match mv {
+            Some(mv) if game.legal_moves().contains(&mv) => mv,
+            _ => return (game, false),
+        };
+        game.play_move(mv);
+        if first_to_move {
+            second.opponent_moved(&game, mv);
+        } else {
+            first.opponent_moved(&game, mv);
+        }
+    }
+    (game, true)
+}
+
+impl Game for Board {
+    type Move = Square;
+
+    fn legal_moves(&self) -> Vec<Square> {
+        (0..9)
+            .filter(|square| self.is_valid_move(*square as i8))
+            .map(|square| Square::new(square).unwrap())
+            .collect()
+    }
+
+    fn play_move(&mut self, mv: Square) {
+        self.play(i8::from(mv)).unwrap();
+    }
+
+    fn current_player(&self) -> i8 {
+        Board::current_player(self)
+    }
+
+    fn situation(&self) -> Outcome {
+        Board::situation(self)
+    }
+}
+
+// the other boards all have the same methods, with their own move types
+macro_rules! impl_game {
+    ($board:ty, $move:ty) => {
+        impl Game for $board {
+            type Move = $move;
+
+            fn legal_moves(&self) -> Vec<$move> {
+                <$board>::legal_moves(self)
+            }
+
+            fn play_move(&mut self, mv: $move) {
+                self.play(mv).unwrap();
+            }
+
+            fn current_player(&self) -> i8 {
+                <$board>::current_player(self)
+            }
+
+            fn situation(&self) -> Outcome {
+                <$board>::situation(self)
+            }
+        }
+    };
+}
+
+impl_game!(MnkBoard, usize);
+impl_game!(UltimateBoard, UltimateMove);
+impl_game!(CubeBoard, usize);
+impl_game!(NotaktoBoard, NotaktoMove);
+impl_game!(WildBoard, WildMove);
+impl_game!(NumericalBoard, NumericalMove);
+impl_game!(MorrisBoard, MorrisMove);
+impl_game!(OrderChaosBoard, OrderChaosMove);
+impl_game!(GomokuBoard, usize);
+impl_game!(QuantumBoard, QuantumMove);
+
+// each searcher plays through its board's `best_move_with`
+macro_rules! impl_engine {
+    ($searcher:ty, $board:ty) => {
+        impl Engine<$board> for $searcher {
+            fn choose_move(&mut self, game: &$board) -> Option<<$board as Game>::Move> {
+                game.clone().best_move_with(self).ok()
+            }
+        }
+    };
+}
+
+impl_engine!(Searcher, Board);
+impl_engine!(MnkSearcher, MnkBoard);
+impl_engine!(UltimateSearcher, UltimateBoard);
+impl_engine!(CubeSearcher, CubeBoard);
+impl_engine!(OrderChaosSearcher, OrderChaosBoard);
+impl_engine!(GomokuSearcher, GomokuBoard);
+impl_engine!(QuantumSearcher, QuantumBoard);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::StrengthPlayer;
+
+    #[test]
+    fn perfect_play_draws() {
+        for seed in 0..10 {
+            let mut first = StrengthPlayer::perfect(seed);
+            let mut second = StrengthPlayer::perfect(seed + 100);
+            let end = play_game(&Board::new(), &mut first, &mut second).unwrap();
+            assert_eq!(end.situation(), Outcome::Draw);
+            // the searcher plays perfectly too
+            let end = play_game(&Board::new(), &mut first, &mut Searcher::new()).unwrap();
+            assert_eq!(end.situation(), Outcome::Draw);
+        }
+    }
+}
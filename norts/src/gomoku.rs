@@ -0,0 +1,450 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::bitboards::WideBitboard;
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+use crate::gomoku_search::GomokuSearcher;
+use crate::mnk::{Geometry, InvalidGeometryError, MnkBoard};
+use crate::wild::Symbol;
+
+/// Width and height of a standard Gomoku board.
+pub const GOMOKU_SIZE: usize = 15;
+/// Width and height of the largest board that fits in a `WideBitboard`.
+pub const MAX_GOMOKU_SIZE: usize = 16;
+const FIVE: usize = 5;
+
+/// The kinds of threat found by `GomokuBoard::threats`, strongest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ThreatKind {
+    /// Five in a row, the game is over.
+    Five,
+    /// Four stones with two squares completing five, so it can't be blocked.
+    OpenFour,
+    /// Four stones with a single square completing five, which must be blocked at once.
+    Four,
+    /// Three stones with empty squares at both ends, one move away from an open four.
+    OpenThree,
+}
+
+impl fmt::Display for ThreatKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ThreatKind::Five => write!(f, "five"),
+            ThreatKind::OpenFour => write!(f, "open four"),
+            ThreatKind::Four => write!(f, "four"),
+            ThreatKind::OpenThree => write!(f, "open three"),
+        }
+    }
+}
+
+/// A group of stones in one line which threatens to make five.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Threat {
+    pub kind: ThreatKind,
+    /// The stones making up the threat, in order along the line.
+    pub stones: Vec<usize>,
+    /// The empty squares which strengthen the threat: squares completing five for a four,
+    /// or squares turning an open three into an open four. Empty for a five.
+    pub gains: Vec<usize>,
+}
+
+// a whole row, column or diagonal, as squares in order
+#[derive(Debug, Clone)]
+struct Ray {
+    squares: Vec<usize>,
+    mask: WideBitboard,
+}
+
+/// Gomoku, or five in a row, on a 15x15 board, built on an `MnkBoard`.
+/// X plays first and five or more in a row of one colour wins.
+///
+/// Besides the usual board methods, `threats` finds fours and open threes, the patterns that decide the game.
+/// The game is far too big to solve, so it is played by `GomokuSearcher`, which looks for a win by
+/// continuous fours before running a depth limited alpha-beta search over the most promising squares.
+/// ``` ignore
+/// use norts::{GomokuBoard, Symbol};
+///
+/// fn main() {
+///     let mut pos = GomokuBoard::parse_pgn("112,113,97,98,127").unwrap();
+///     pos.show();
+///     for threat in pos.threats(Symbol::X) {
+///         println!("{} at {:?}", threat.kind, threat.stones);
+///     }
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GomokuBoard {
+    board: MnkBoard,
+    // every ray long enough to hold five
+    rays: Arc<Vec<Ray>>,
+}
+
+impl GomokuBoard {
+    /// Returns an empty 15x15 board.
+    pub fn new() -> GomokuBoard {
+        GomokuBoard::with_size(GOMOKU_SIZE).unwrap()
+    }
+
+    /// Returns an empty `size` by `size` board.
+    /// Returns `InvalidGeometryError` unless the board is at least 5 and at most `MAX_GOMOKU_SIZE` wide.
+    pub fn with_size(size: usize) -> Result<GomokuBoard, InvalidGeometryError> {
+        if !(FIVE..=MAX_GOMOKU_SIZE).contains(&size) {
+            return Err(InvalidGeometryError);
+        }
+        let geometry = Arc::new(Geometry::new(size, size, FIVE)?);
+        let mut rays = Vec::new();
+        for (dx, dy) in [(1, 0), (0, 1), (1, 1), (-1, 1)] {
+            for row in 0..size as isize {
+                for col in 0..size as isize {
+                    let on_board = |col: isize, row: isize| {
+                        (0..size as isize).contains(&col) && (0..size as isize).contains(&row)
+                    };
+                    // rays start where the previous square is off the board
+                    if on_board(col - dx, row - dy) {
+                        continue;
+                    }
+                    let mut ray = Vec::new();
+                    let (mut c, mut r) = (col, row);
+                    while on_board(c, r) {
+                        ray.push(r as usize * size + c as usize);
+                        c += dx;
+                        r += dy;
+                    }
+                    if ray.len() >= FIVE {
+                        let mut mask = WideBitboard::new();
+                        ray.iter().for_each(|square| mask.set(*square));
+                        rays.push(Ray { squares: ray, mask });
+                    }
+                }
+            }
+        }
+        Ok(GomokuBoard {
+            board: MnkBoard::with_geometry(geometry),
+            rays: Arc::new(rays),
+        })
+    }
+
+    /// Returns a 15x15 board which picks up from the specified PGN string of comma separated squares, e.g. "112,113".
+    pub fn parse_pgn(pgn: &str) -> Result<GomokuBoard, InvalidPgnError> {
+        GomokuBoard::parse_pgn_with_size(GOMOKU_SIZE, pgn)
+    }
+
+    /// Returns a `size` by `size` board which picks up from the specified PGN string.
+    pub fn parse_pgn_with_size(size: usize, pgn: &str) -> Result<GomokuBoard, InvalidPgnError> {
+        let mut pos = GomokuBoard::with_size(size).map_err(|_| InvalidPgnError)?;
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let square = part.parse().map_err(|_| InvalidPgnError)?;
+            pos.play(square).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// The underlying m,n,k board, which holds the stones and the lines of five.
+    pub fn board(&self) -> &MnkBoard {
+        &self.board
+    }
+
+    pub fn geometry(&self) -> &Arc<Geometry> {
+        self.board.geometry()
+    }
+
+    pub fn size(&self) -> usize {
+        self.board.geometry().width()
+    }
+
+    /// Returns the moves played so far, see `GomokuBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.board.pgn()
+    }
+
+    pub fn history(&self) -> &[usize] {
+        self.board.history()
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.board.num_moves()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        self.board.current_player()
+    }
+
+    /// Returns the colour to move.
+    pub fn current_symbol(&self) -> Symbol {
+        if self.board.x_to_move() {
+            Symbol::X
+        } else {
+            Symbol::O
+        }
+    }
+
+    pub fn is_empty(&self, square: usize) -> bool {
+        self.board.is_empty(square)
+    }
+
+    pub fn is_valid_move(&self, square: usize) -> bool {
+        self.board.is_valid_move(square)
+    }
+
+    pub fn legal_moves(&self) -> Vec<usize> {
+        self.board.legal_moves()
+    }
+
+    pub fn play(&mut self, square: usize) -> Result<bool, IllegalMoveError> {
+        self.board.play(square)
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        self.board.undo_move()
+    }
+
+    #[inline(always)]
+    pub(crate) fn play_unchecked(&mut self, square: usize) {
+        self.board.play_unchecked(square)
+    }
+
+    #[inline(always)]
+    pub(crate) fn undo_unchecked(&mut self) {
+        self.board.undo_unchecked()
+    }
+
+    #[inline(always)]
+    pub(crate) fn last_move_won(&self) -> bool {
+        self.board.last_move_won()
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        self.board.is_full()
+    }
+
+    // the stones of the given colour and of their opponent
+    #[inline(always)]
+    pub(crate) fn stones(&self, symbol: Symbol) -> (&WideBitboard, &WideBitboard) {
+        match symbol {
+            Symbol::X => (&self.board.x_bitboard, &self.board.o_bitboard),
+            Symbol::O => (&self.board.o_bitboard, &self.board.x_bitboard),
+        }
+    }
+
+    /// Returns the empty squares in lines of five holding `stones` stones of the given colour and none of the other.
+    /// With 4 these are the squares that win at once, with 3 the squares that make a four.
+    pub(crate) fn completing_squares(&self, symbol: Symbol, stones: u32) -> WideBitboard {
+        let (own, opp) = self.stones(symbol);
+        let mut squares = WideBitboard::new();
+        for line in self.geometry().lines() {
+            if !opp.intersects(line) && own.intersection(line).count_ones() == stones {
+                squares = squares.union(&line.difference(own));
+            }
+        }
+        squares
+    }
+
+    /// Returns the squares where the given colour would complete five, in square order.
+    pub fn winning_squares(&self, symbol: Symbol) -> Vec<usize> {
+        self.completing_squares(symbol, 4).squares().collect()
+    }
+
+    /// Finds every five, four and open three of the given colour, see `ThreatKind`.
+    /// Threats are found one row, column or diagonal at a time,
+    /// so stones in two directions at once show up in a threat for each.
+    pub fn threats(&self, symbol: Symbol) -> Vec<Threat> {
+        let (own, opp) = self.stones(symbol);
+        let mut threats = Vec::new();
+        for ray in self.rays.iter() {
+            let ray = &ray.squares;
+            let is_own = |i: usize| own.get(ray[i]);
+            let is_empty = |i: usize| !own.get(ray[i]) && !opp.get(ray[i]);
+            // threats in this ray, grouped by their stones so that two gains for the same stones are one threat
+            let mut found: Vec<(ThreatKind, Vec<usize>, Vec<usize>)> = Vec::new();
+            let mut add = |kind: ThreatKind, stones: Vec<usize>, gain: Option<usize>| match found
+                .iter_mut()
+                .find(|(found_kind, found_stones, _)| {
+                    *found_kind == kind && *found_stones == stones
+                }) {
+                Some((_, _, gains)) => gains.extend(gain),
+                None => found.push((kind, stones, gain.into_iter().collect())),
+            };
+            for start in 0..=ray.len() - FIVE {
+                let window = start..start + FIVE;
+                let stones: Vec<usize> = window
+                    .clone()
+                    .filter(|i| is_own(*i))
+                    .map(|i| ray[i])
+                    .collect();
+                let empty: Vec<usize> = window.filter(|i| is_empty(*i)).map(|i| ray[i]).collect();
+                if stones.len() == FIVE {
+                    add(ThreatKind::Five, stones, None);
+                } else if stones.len() == FIVE - 1 && empty.len() == 1 {
+                    add(ThreatKind::Four, stones, Some(empty[0]));
+                }
+            }
+            // an open three is _XXX__, __XXX_, _XX_X_ or _X_XX_, not touching another stone of its colour
+            for start in 0..ray.len().saturating_sub(FIVE) {
+                let end = start + FIVE;
+                if !is_empty(start)
+                    || !is_empty(end)
+                    || (start > 0 && is_own(start - 1))
+                    || (end + 1 < ray.len() && is_own(end + 1))
+                {
+                    continue;
+                }
+                let inside = start + 1..end;
+                let stones: Vec<usize> = inside
+                    .clone()
+                    .filter(|i| is_own(*i))
+                    .map(|i| ray[i])
+                    .collect();
+                let empty: Vec<usize> = inside.filter(|i| is_empty(*i)).map(|i| ray[i]).collect();
+                if stones.len() == 3 && empty.len() == 1 {
+                    add(ThreatKind::OpenThree, stones, Some(empty[0]));
+                }
+            }
+            for (kind, stones, mut gains) in found {
+                gains.sort_unstable();
+                gains.dedup();
+                let kind = if kind == ThreatKind::Four && gains.len() > 1 {
+                    ThreatKind::OpenFour
+                } else {
+                    kind
+                };
+                threats.push(Threat {
+                    kind,
+                    stones,
+                    gains,
+                });
+            }
+        }
+        threats.sort_by_key(|threat| threat.kind);
+        threats
+    }
+
+    /// Counts the open threes of the given colour, as found by `GomokuBoard::threats` but without building them.
+    pub(crate) fn count_open_threes(&self, symbol: Symbol) -> usize {
+        let (own, opp) = self.stones(symbol);
+        let mut count = 0;
+        for ray in self.rays.iter() {
+            if own.intersection(&ray.mask).count_ones() < 3 {
+                continue;
+            }
+            let ray = &ray.squares;
+            let is_own = |i: usize| own.get(ray[i]);
+            let is_empty = |i: usize| !own.get(ray[i]) && !opp.get(ray[i]);
+            // the same three can fill two neighbouring windows, so it is only counted when its stones change
+            let mut last_stones: u32 = 0;
+            for start in 0..ray.len().saturating_sub(FIVE) {
+                let end = start + FIVE;
+                if !is_empty(start)
+                    || !is_empty(end)
+                    || (start > 0 && is_own(start - 1))
+                    || (end + 1 < ray.len() && is_own(end + 1))
+                {
+                    continue;
+                }
+                let stones: u32 = (start + 1..end)
+                    .filter(|i| is_own(*i))
+                    .map(|i| 1 << i)
+                    .sum();
+                let empty = (start + 1..end).filter(|i| is_empty(*i)).count();
+                if stones.count_ones() == 3 && empty == 1 && stones != last_stones {
+                    count += 1;
+                    last_stones = stones;
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns whether X or O has won, the game is drawn or it is still ongoing.
+    pub fn situation(&self) -> Outcome {
+        self.board.situation()
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.board.is_in_play()
+    }
+
+    /// Returns the best move found by a default `GomokuSearcher`.
+    pub fn best_move(&mut self) -> Result<usize, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut GomokuSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut GomokuSearcher,
+    ) -> Result<usize, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            Ok(searcher.search(self).best_move.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        self.board.show()
+    }
+}
+
+impl Default for GomokuBoard {
+    fn default() -> Self {
+        GomokuBoard::new()
+    }
+}
+
+impl fmt::Display for GomokuBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn board_sizes() {
+        assert!(GomokuBoard::with_size(FIVE - 1).is_err());
+        assert_eq!(GomokuBoard::with_size(FIVE).unwrap().size(), FIVE);
+        assert_eq!(
+            GomokuBoard::with_size(MAX_GOMOKU_SIZE).unwrap().size(),
+            MAX_GOMOKU_SIZE
+        );
+        assert!(GomokuBoard::with_size(MAX_GOMOKU_SIZE + 1).is_err());
+        assert!(GomokuBoard::with_size(100).is_err());
+    }
+}
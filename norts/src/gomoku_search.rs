@@ -0,0 +1,478 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+
+use crate::bitboards::WideBitboard;
+use crate::evaluation::Evaluation;
+use crate::gomoku::GomokuBoard;
+use crate::iterative::{
+    to_evaluation, IterativeSearch, Node, SearchPosition, HEURISTIC_LIMIT, WIN,
+};
+use crate::wild::Symbol;
+
+/// Nodes searched by a default `GomokuSearcher` before it settles for its last completed depth.
+pub const GOMOKU_NODE_LIMIT: u64 = 100_000;
+/// Fours a default `GomokuSearcher` will play in a row looking for a win, see `GomokuSearcher::vcf`.
+pub const DEFAULT_VCF_DEPTH: u8 = 12;
+/// Squares a default `GomokuSearcher` considers in each position.
+pub const DEFAULT_BRANCHING: usize = 10;
+
+// value of a line of five holding n stones of one colour and none of the other
+const LINE_VALUES: [i32; 5] = [0, 1, 8, 64, 512];
+// value of playing into a line holding n of your own stones, or n of the opponent's
+const ATTACK_VALUES: [i32; 5] = [1, 4, 32, 256, 100_000];
+const DEFENCE_VALUES: [i32; 5] = [0, 3, 24, 200, 50_000];
+// pattern scores at the horizon, where neither side has a four
+const OPEN_THREE_TO_MOVE: i32 = 20_000;
+const DOUBLE_OPEN_THREE: i32 = 10_000;
+const OPEN_THREE: i32 = 1_000;
+
+/// The result of searching a `GomokuBoard`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GomokuSearchResult {
+    pub best_move: Option<usize>,
+    /// Score from the point of view of the player to move.
+    /// Wins are ±1,000,000,000 less the length of the game, anything else is a heuristic estimate.
+    pub score: i32,
+    /// The exact value of the position if the search found a forced win.
+    pub evaluation: Option<Evaluation>,
+    /// The winning sequence of fours and replies if the position was won by `GomokuSearcher::vcf`.
+    pub vcf: Option<Vec<usize>>,
+    /// Depth of the last completed iteration, 0 if the position was won by continuous fours.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Threat-space search for `GomokuBoard`s.
+///
+/// Each search first tries `GomokuSearcher::vcf`, looking for a win by continuous fours,
+/// then runs an iterative deepening alpha-beta search with a transposition table until the maximum depth
+/// is reached or the node budget runs out.
+/// The alpha-beta search only looks at the most promising squares near the stones already played,
+/// ranked by how many lines they extend or block, and forced replies to fours are searched without using depth.
+/// Positions at the horizon are scored by counting open lines and the open threes of each side.
+/// ``` ignore
+/// use norts::{GomokuBoard, GomokuSearcher};
+///
+/// fn main() {
+///     let mut pos = GomokuBoard::parse_pgn("112,113,97,98,127").unwrap();
+///     let result = GomokuSearcher::new().with_node_limit(50_000).search(&mut pos);
+///     println!("{} scores {} after {} nodes", result.best_move.unwrap(), result.score, result.nodes);
+/// }
+/// ```
+pub struct GomokuSearcher {
+    search: IterativeSearch<(WideBitboard, WideBitboard), usize>,
+    vcf_depth: u8,
+    branching: usize,
+    // positions with no win by continuous fours for the player to move, and the depth they were searched to
+    vcf_failures: HashMap<(WideBitboard, WideBitboard), u8>,
+    // width of the boards in the tables, the same stones mean something else on another size
+    size: Option<usize>,
+}
+
+impl GomokuSearcher {
+    /// Returns a searcher with no depth limit, a budget of `GOMOKU_NODE_LIMIT` nodes per search,
+    /// `DEFAULT_VCF_DEPTH` and `DEFAULT_BRANCHING`.
+    pub fn new() -> GomokuSearcher {
+        GomokuSearcher {
+            search: IterativeSearch::new(GOMOKU_NODE_LIMIT),
+            vcf_depth: DEFAULT_VCF_DEPTH,
+            branching: DEFAULT_BRANCHING,
+            vcf_failures: HashMap::new(),
+            size: None,
+        }
+    }
+
+    /// Limits how many moves ahead the alpha-beta search looks.
+    pub fn with_max_depth(mut self, depth: u8) -> GomokuSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit, counting the search for continuous fours.
+    pub fn with_node_limit(mut self, nodes: u64) -> GomokuSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Limits how many fours in a row the search for continuous fours will play.
+    pub fn with_vcf_depth(mut self, depth: u8) -> GomokuSearcher {
+        self.vcf_depth = depth;
+        self
+    }
+
+    /// Sets how many squares the alpha-beta search considers in each position.
+    pub fn with_branching(mut self, branching: usize) -> GomokuSearcher {
+        self.branching = branching.max(1);
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+        self.vcf_failures.clear();
+    }
+
+    /// Searches the position, see `GomokuSearcher`.
+    /// The tables are cleared if the board is a different size to the last one.
+    pub fn search(&mut self, pos: &mut GomokuBoard) -> GomokuSearchResult {
+        self.search.reset();
+        let mut result = GomokuSearchResult {
+            best_move: None,
+            score: 0,
+            evaluation: None,
+            vcf: None,
+            depth: 0,
+            nodes: 0,
+        };
+        if !pos.is_in_play() {
+            return result;
+        }
+        if let Some(line) = self.find_vcf(pos) {
+            result.best_move = Some(line[0]);
+            result.score = WIN - (pos.num_moves() + line.len()) as i32;
+            result.evaluation = Some(to_evaluation(x_score(result.score, pos), pos.num_moves()));
+            result.vcf = Some(line);
+            result.nodes = self.search.nodes();
+            return result;
+        }
+        let remaining = pos.geometry().num_squares() - pos.num_moves();
+        let mut node = GomokuNode {
+            pos,
+            branching: self.branching,
+        };
+        let iteration = self.search.search(&mut node, remaining);
+        result.best_move = iteration.best_move;
+        result.score = iteration.score;
+        result.depth = iteration.depth;
+        if iteration.solved {
+            result.evaluation = Some(to_evaluation(
+                x_score(iteration.score, pos),
+                pos.num_moves(),
+            ));
+        }
+        result.nodes = self.search.nodes();
+        result
+    }
+
+    /// Looks for a victory by continuous fours for the player to move: a sequence of moves which each make a four,
+    /// so the opponent's reply is forced, ending in a double four or five.
+    /// Returns the whole sequence, both sides' moves, ending with the move that makes five,
+    /// or `None` if there is no such win within the VCF depth and node budget.
+    pub fn vcf(&mut self, pos: &mut GomokuBoard) -> Option<Vec<usize>> {
+        self.search.reset();
+        if !pos.is_in_play() {
+            return None;
+        }
+        self.find_vcf(pos)
+    }
+
+    fn find_vcf(&mut self, pos: &mut GomokuBoard) -> Option<Vec<usize>> {
+        if self.size != Some(pos.size()) {
+            self.clear();
+            self.size = Some(pos.size());
+        }
+        let mut line = Vec::new();
+        if self.vcf_search(pos, self.vcf_depth, &mut line) {
+            Some(line)
+        } else {
+            None
+        }
+    }
+
+    // returns true if the player to move wins by continuous fours, leaving the winning line in `line`
+    fn vcf_search(&mut self, pos: &mut GomokuBoard, depth: u8, line: &mut Vec<usize>) -> bool {
+        if !self.search.visit() {
+            return false;
+        }
+        let attacker = pos.current_symbol();
+        let defender = opponent(attacker);
+        if let Some(square) = pos.completing_squares(attacker, 4).squares().next() {
+            line.push(square);
+            return true;
+        }
+        let key = (pos.board().x_bitboard, pos.board().o_bitboard);
+        if depth == 0
+            || self
+                .vcf_failures
+                .get(&key)
+                .is_some_and(|searched| *searched >= depth)
+        {
+            return false;
+        }
+        let defender_wins = pos.completing_squares(defender, 4);
+        let mut fours = pos.completing_squares(attacker, 3);
+        match defender_wins.count_ones() {
+            0 => (),
+            // the defender's four must be blocked, so that is the only square worth trying
+            1 => fours = fours.intersection(&defender_wins),
+            _ => return false,
+        }
+        for square in fours.squares().collect::<Vec<_>>() {
+            pos.play_unchecked(square);
+            let wins = pos.completing_squares(attacker, 4);
+            let won = match wins.count_ones() {
+                0 => false,
+                1 => {
+                    let block = wins.squares().next().unwrap();
+                    pos.play_unchecked(block);
+                    let mut rest = Vec::new();
+                    // a block which makes a five for the defender ends the line
+                    let won = !pos.last_move_won() && self.vcf_search(pos, depth - 1, &mut rest);
+                    pos.undo_unchecked();
+                    if won {
+                        line.push(square);
+                        line.push(block);
+                        line.append(&mut rest);
+                    }
+                    won
+                }
+                _ => {
+                    // a double four, the defender can only block one of them
+                    let mut squares = wins.squares();
+                    let block = squares.next().unwrap();
+                    line.extend([square, block, squares.next().unwrap()]);
+                    true
+                }
+            };
+            pos.undo_unchecked();
+            if won {
+                return true;
+            }
+            if self.search.aborted() {
+                return false;
+            }
+        }
+        self.vcf_failures.insert(key, depth);
+        false
+    }
+}
+
+impl Default for GomokuSearcher {
+    fn default() -> Self {
+        GomokuSearcher::new()
+    }
+}
+
+// a position together with how many squares to consider in it
+struct GomokuNode<'a> {
+    pos: &'a mut GomokuBoard,
+    branching: usize,
+}
+
+impl SearchPosition for GomokuNode<'_> {
+    type Move = usize;
+    type Key = (WideBitboard, WideBitboard);
+
+    fn key(&self) -> Self::Key {
+        (self.pos.board().x_bitboard, self.pos.board().o_bitboard)
+    }
+
+    fn mover(&self) -> bool {
+        self.pos.current_player() == 1
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<usize> {
+        let pos = &*self.pos;
+        if pos.last_move_won() {
+            // the player who just moved won
+            return Node::Solved(-(WIN - pos.num_moves() as i32));
+        }
+        if pos.is_full() {
+            return Node::Solved(0);
+        }
+        let scan = scan_lines(pos);
+        if !scan.wins.is_empty() {
+            return Node::Solved(WIN - pos.num_moves() as i32 - 1);
+        }
+        match scan.threats.count_ones() {
+            0 if depth == 0 => Node::Horizon(evaluate(pos, scan.score)),
+            0 => Node::Search,
+            // blocking a four is the only move
+            1 => Node::Forced(scan.threats.squares().collect()),
+            // only one of two fives can be blocked
+            _ => Node::Solved(-(WIN - pos.num_moves() as i32 - 2)),
+        }
+    }
+
+    fn ordered_moves(&mut self) -> (Vec<usize>, bool) {
+        let (moves, truncated) = ordered_moves(self.pos, self.branching);
+        (moves, !truncated)
+    }
+
+    fn play(&mut self, square: usize) {
+        self.pos.play_unchecked(square);
+    }
+
+    fn undo(&mut self) {
+        self.pos.undo_unchecked();
+    }
+}
+
+// returns the most promising empty squares within two of a stone, best first,
+// and whether any were left out by the branching limit
+fn ordered_moves(pos: &GomokuBoard, branching: usize) -> (Vec<usize>, bool) {
+    let geometry = pos.geometry();
+    let size = geometry.width();
+    if pos.num_moves() == 0 {
+        return (vec![geometry.centre_order()[0]], false);
+    }
+    let player = pos.current_symbol();
+    let (own, opp) = pos.stones(player);
+    let mut near = WideBitboard::new();
+    for stone in own.union(opp).squares() {
+        let (row, col) = ((stone / size) as isize, (stone % size) as isize);
+        for r in (row - 2).max(0)..=(row + 2).min(size as isize - 1) {
+            for c in (col - 2).max(0)..=(col + 2).min(size as isize - 1) {
+                near.set(r as usize * size + c as usize);
+            }
+        }
+    }
+    let mut moves: Vec<(i32, usize)> = near
+        .difference(&own.union(opp))
+        .squares()
+        .map(|square| {
+            let mut value = 0;
+            for line in geometry.lines_through(square) {
+                let line = &geometry.lines()[*line];
+                let mine = own.intersection(line).count_ones() as usize;
+                let theirs = opp.intersection(line).count_ones() as usize;
+                if theirs == 0 {
+                    value += ATTACK_VALUES[mine];
+                } else if mine == 0 {
+                    value += DEFENCE_VALUES[theirs];
+                }
+            }
+            (value, square)
+        })
+        .collect();
+    moves.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    let truncated = moves.len() > branching;
+    moves.truncate(branching);
+    (
+        moves.into_iter().map(|(_, square)| square).collect(),
+        truncated,
+    )
+}
+
+#[inline(always)]
+fn opponent(symbol: Symbol) -> Symbol {
+    match symbol {
+        Symbol::X => Symbol::O,
+        Symbol::O => Symbol::X,
+    }
+}
+
+// what a single pass over the lines of five finds, from the point of view of the player to move
+struct LineScan {
+    // squares where the player to move makes five
+    wins: WideBitboard,
+    // squares where the opponent makes five
+    threats: WideBitboard,
+    // open lines for the player to move less open lines for the opponent, see `LINE_VALUES`
+    score: i32,
+}
+
+fn scan_lines(pos: &GomokuBoard) -> LineScan {
+    let (own, opp) = pos.stones(pos.current_symbol());
+    let mut scan = LineScan {
+        wins: WideBitboard::new(),
+        threats: WideBitboard::new(),
+        score: 0,
+    };
+    for line in pos.geometry().lines() {
+        let mine = own.intersection(line).count_ones() as usize;
+        let theirs = opp.intersection(line).count_ones() as usize;
+        if theirs == 0 {
+            scan.score += LINE_VALUES[mine];
+            if mine == 4 {
+                scan.wins = scan.wins.union(&line.difference(own));
+            }
+        } else if mine == 0 {
+            scan.score -= LINE_VALUES[theirs];
+            if theirs == 4 {
+                scan.threats = scan.threats.union(&line.difference(opp));
+            }
+        }
+    }
+    scan
+}
+
+// scores a position where neither side has a four from the point of view of the player to move,
+// adding the open threes of each side to the score for open lines
+fn evaluate(pos: &GomokuBoard, line_score: i32) -> i32 {
+    let player = pos.current_symbol();
+    let mut score = line_score;
+    // an open three for the player to move becomes an open four next move
+    if pos.count_open_threes(player) > 0 {
+        score += OPEN_THREE_TO_MOVE;
+    }
+    score -= match pos.count_open_threes(opponent(player)) {
+        0 => 0,
+        1 => OPEN_THREE,
+        _ => DOUBLE_OPEN_THREE,
+    };
+    score.clamp(-HEURISTIC_LIMIT, HEURISTIC_LIMIT)
+}
+
+// converts a score for the player to move into one for X
+fn x_score(score: i32, pos: &GomokuBoard) -> i32 {
+    if pos.current_player() == 1 {
+        score
+    } else {
+        -score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gomoku::ThreatKind;
+
+    #[test]
+    fn vcf_finds_a_double_four() {
+        // X has a closed three along row 7 and another down column 7, both ending next to square 112,
+        // and O's stones are out of the way in the corners
+        let pgn = "109,108,110,52,111,0,67,14,82,210,97,224";
+        let mut pos = GomokuBoard::parse_pgn(pgn).unwrap();
+        let mut searcher = GomokuSearcher::new();
+        let line = searcher.vcf(&mut pos).unwrap();
+        assert_eq!(line.len(), 3);
+        assert_eq!(line[0], 112);
+        // O blocks one four and X completes the other
+        assert!([113, 127].contains(&line[1]));
+        assert!([113, 127].contains(&line[2]));
+        assert_ne!(line[1], line[2]);
+
+        let result = searcher.search(&mut pos);
+        assert_eq!(result.best_move, Some(112));
+        assert_eq!(result.evaluation, Some(Evaluation::XWinsIn(3)));
+
+        pos.play(112).unwrap();
+        let fours = pos
+            .threats(Symbol::X)
+            .into_iter()
+            .filter(|threat| threat.kind == ThreatKind::Four)
+            .count();
+        assert_eq!(fours, 2);
+    }
+}
@@ -0,0 +1,220 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::bitboards::{has_line, Bitboard};
+use crate::retrograde::{solve, Value};
+use crate::rng::Rng;
+use crate::strength::Strength;
+
+/// Number of marks each player keeps under `Rules::Infinite`.
+pub(crate) const MARKS_PER_PLAYER: usize = 3;
+const WINDOW: usize = 2 * MARKS_PER_PLAYER;
+/// A position coming up this many times in a game is a draw.
+pub(crate) const REPETITIONS_FOR_DRAW: usize = 3;
+
+static SOLUTION: OnceLock<HashMap<InfiniteState, Value>> = OnceLock::new();
+
+/// A position under `Rules::Infinite`. The marks on the board are the last six moves,
+/// and their order matters as it decides which mark vanishes next,
+/// so the state is those moves, oldest first, along with whose turn it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct InfiniteState {
+    recent: [u8; WINDOW],
+    len: u8,
+    x_to_move: bool,
+}
+
+impl InfiniteState {
+    pub(crate) fn new() -> InfiniteState {
+        InfiniteState {
+            recent: [0; WINDOW],
+            len: 0,
+            x_to_move: true,
+        }
+    }
+
+    /// Replays a game given in PGN, see `Board::parse_pgn`, without checking the moves.
+    pub(crate) fn from_pgn(pgn: &str) -> InfiniteState {
+        pgn.chars()
+            .filter_map(|c| c.to_digit(10))
+            .fold(InfiniteState::new(), |state, square| {
+                state.play(square as u8)
+            })
+    }
+
+    /// Returns the marks on the board.
+    pub(crate) fn marks(&self) -> Bitboard {
+        let mut pos = Bitboard::new();
+        // the most recent move was made by the player not to move, and the moves before it alternate
+        let mut x_moved = !self.x_to_move;
+        for square in self.recent[..self.len as usize].iter().rev() {
+            if x_moved {
+                pos.x_bitboard |= 1 << square;
+            } else {
+                pos.o_bitboard |= 1 << square;
+            }
+            x_moved = !x_moved;
+        }
+        pos
+    }
+
+    pub(crate) fn is_legal(&self, square: u8) -> bool {
+        square < 9 && !self.recent[..self.len as usize].contains(&square)
+    }
+
+    /// Returns the state after a move, with the mover's oldest mark gone if they already had three.
+    pub(crate) fn play(&self, square: u8) -> InfiniteState {
+        let mut next = *self;
+        if next.len as usize == WINDOW {
+            next.recent.copy_within(1.., 0);
+            next.recent[WINDOW - 1] = square;
+        } else {
+            next.recent[next.len as usize] = square;
+            next.len += 1;
+        }
+        next.x_to_move = !self.x_to_move;
+        next
+    }
+
+    /// Returns true if the player who just moved has a line, so the player to move has lost.
+    pub(crate) fn is_lost(&self) -> bool {
+        let marks = self.marks();
+        if self.x_to_move {
+            has_line(marks.o_bitboard)
+        } else {
+            has_line(marks.x_bitboard)
+        }
+    }
+
+    fn children(&self) -> Vec<InfiniteState> {
+        (0..9)
+            .filter(|square| self.is_legal(*square))
+            .map(|square| self.play(square))
+            .collect()
+    }
+}
+
+/// Returns the value of every reachable position, solving the game the first time it is needed.
+pub(crate) fn solution() -> &'static HashMap<InfiniteState, Value> {
+    SOLUTION.get_or_init(|| {
+        solve(InfiniteState::new(), InfiniteState::children, |state| {
+            state.is_lost().then_some(Value::Loss(0))
+        })
+    })
+}
+
+/// Returns the value of each legal move for the player making it, in square order.
+pub(crate) fn move_values(state: &InfiniteState) -> Vec<(u8, Value)> {
+    let solution = solution();
+    (0..9)
+        .filter(|square| state.is_legal(*square))
+        .map(|square| (square, solution[&state.play(square)].for_mover()))
+        .collect()
+}
+
+/// Returns the best moves in the position along with their value, see `Value::rank`.
+pub(crate) fn best_moves(state: &InfiniteState) -> (Value, Vec<u8>) {
+    let values = move_values(state);
+    let best = values
+        .iter()
+        .map(|(_, value)| *value)
+        .max_by_key(|value| value.rank())
+        .unwrap();
+    let moves = values
+        .into_iter()
+        .filter(|(_, value)| *value == best)
+        .map(|(square, _)| square)
+        .collect();
+    (best, moves)
+}
+
+/// Picks a move at the given strength, the counterpart of `strength::choose_move` for `Rules::Infinite`.
+/// A depth limited engine only sees wins and losses within its depth, anything further away looks like a draw.
+pub(crate) fn choose_move(state: &InfiniteState, strength: Strength, rng: &mut Rng) -> Option<u8> {
+    if state.is_lost() {
+        return None;
+    }
+    let values = move_values(state);
+    let tied_best = |rank: &dyn Fn(Value) -> i32| -> Vec<u8> {
+        let best = values.iter().map(|(_, value)| rank(*value)).max().unwrap();
+        values
+            .iter()
+            .filter(|(_, value)| rank(*value) == best)
+            .map(|(square, _)| *square)
+            .collect()
+    };
+    let legal: Vec<u8> = values.iter().map(|(square, _)| *square).collect();
+    let moves = match strength {
+        Strength::Random => legal,
+        Strength::DepthLimited(depth) => tied_best(&|value| match value {
+            Value::Win(n) | Value::Loss(n) if n > u16::from(depth) => 0,
+            value => value.rank(),
+        }),
+        Strength::Blunder(probability) => {
+            // a slower win is still a win, only moves which change the result count as mistakes
+            let result = |value: Value| value.rank().signum();
+            let best_result = values.iter().map(|(_, value)| result(*value)).max();
+            let mistakes: Vec<u8> = values
+                .iter()
+                .filter(|(_, value)| Some(result(*value)) < best_result)
+                .map(|(square, _)| *square)
+                .collect();
+            if !mistakes.is_empty() && rng.chance(probability) {
+                mistakes
+            } else {
+                tied_best(&Value::rank)
+            }
+        }
+        Strength::EpsilonOptimal(epsilon) => {
+            if rng.chance(epsilon) {
+                legal
+            } else {
+                tied_best(&Value::rank)
+            }
+        }
+        Strength::Perfect => tied_best(&Value::rank),
+    };
+    rng.choose(&moves).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blunders_change_the_result() {
+        let mut rng = Rng::new(1);
+        for state in solution().keys().filter(|state| !state.is_lost()) {
+            let values = move_values(state);
+            let best = values.iter().map(|(_, value)| value.rank().signum()).max();
+            let square = choose_move(state, Strength::Blunder(1.0), &mut rng).unwrap();
+            let (_, value) = values.iter().find(|(mv, _)| *mv == square).unwrap();
+            // a blunder is only avoided when every move has the same result
+            let all_same = values
+                .iter()
+                .all(|(_, value)| Some(value.rank().signum()) == best);
+            assert!(all_same || Some(value.rank().signum()) < best);
+        }
+    }
+}
@@ -0,0 +1,332 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::evaluation::Evaluation;
+use crate::transposition::Bound;
+
+/// Score of a win at move 0, wins are scored as `WIN - game length` so quicker wins are preferred.
+pub(crate) const WIN: i32 = 1_000_000_000;
+// heuristic scores are kept well clear of win scores
+pub(crate) const HEURISTIC_LIMIT: i32 = WIN / 2;
+const INFINITY: i32 = i32::MAX;
+
+/// What to do with a position below the root, see `SearchPosition::expand`.
+pub(crate) enum Node<M> {
+    /// The result is known, scored for the player to move.
+    Solved(i32),
+    /// The search stops here with a heuristic score for the player to move.
+    Horizon(i32),
+    /// Only these moves need searching, and they don't use up depth.
+    Forced(Vec<M>),
+    /// Search `SearchPosition::ordered_moves` one move less deep.
+    Search,
+}
+
+/// A position `IterativeSearch` can work on. Moves are played and undone in place.
+pub(crate) trait SearchPosition {
+    type Move: Copy + PartialEq;
+    type Key: Hash + Eq;
+
+    fn key(&self) -> Self::Key;
+
+    /// Tells the players apart. A move which leaves the same player to move keeps the sign of the score.
+    fn mover(&self) -> bool;
+
+    /// Decides how to search a position below the root with `depth` moves left,
+    /// which must not be `Node::Search` when `depth` is 0.
+    fn expand(&mut self, depth: u8) -> Node<Self::Move>;
+
+    /// Returns the moves to search, best first, and whether they are every move worth playing.
+    /// If some were left out a position is only solved by finding a proven win.
+    fn ordered_moves(&mut self) -> (Vec<Self::Move>, bool);
+
+    fn play(&mut self, mv: Self::Move);
+
+    fn undo(&mut self);
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry<M> {
+    score: i32,
+    bound: Bound,
+    depth: u8,
+    // the subtree was searched to the end of the game, so the score is exact regardless of depth
+    solved: bool,
+    best_move: Option<M>,
+}
+
+/// The last completed iteration of `IterativeSearch::search`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Iteration<M> {
+    pub(crate) best_move: Option<M>,
+    /// Score from the point of view of the player to move.
+    pub(crate) score: i32,
+    pub(crate) depth: u8,
+    /// The score is exact rather than heuristic.
+    pub(crate) solved: bool,
+}
+
+/// Iterative deepening alpha-beta search with a transposition table, shared by the searchers for the bigger games.
+/// Each iteration searches one move deeper until the position is solved,
+/// the maximum depth is reached or the node budget runs out, in which case the last completed iteration is used.
+pub(crate) struct IterativeSearch<K, M> {
+    pub(crate) max_depth: Option<u8>,
+    pub(crate) node_limit: u64,
+    table: HashMap<K, Entry<M>>,
+    nodes: u64,
+    aborted: bool,
+}
+
+impl<K: Hash + Eq, M: Copy + PartialEq> IterativeSearch<K, M> {
+    pub(crate) fn new(node_limit: u64) -> IterativeSearch<K, M> {
+        IterativeSearch {
+            max_depth: None,
+            node_limit,
+            table: HashMap::new(),
+            nodes: 0,
+            aborted: false,
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub(crate) fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    pub(crate) fn aborted(&self) -> bool {
+        self.aborted
+    }
+
+    /// Starts the node count again for a new search.
+    pub(crate) fn reset(&mut self) {
+        self.nodes = 0;
+        self.aborted = false;
+    }
+
+    /// Counts a node, returning false once the budget has run out.
+    pub(crate) fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes > self.node_limit {
+            self.aborted = true;
+        }
+        !self.aborted
+    }
+
+    /// Searches a position which is still in play, going no deeper than the moves `remaining` in the game.
+    /// If the budget runs out before the first iteration finishes, the first of the ordered moves is returned
+    /// at depth 0.
+    pub(crate) fn search<P>(&mut self, pos: &mut P, remaining: usize) -> Iteration<M>
+    where
+        P: SearchPosition<Key = K, Move = M>,
+    {
+        // boards can have more than 255 squares
+        let remaining = remaining.min(u8::MAX as usize) as u8;
+        let max_depth = self.max_depth.unwrap_or(remaining).min(remaining).max(1);
+        let (mut moves, complete) = pos.ordered_moves();
+        let mut result = Iteration {
+            best_move: moves.first().copied(),
+            score: 0,
+            depth: 0,
+            solved: false,
+        };
+        for depth in 1..=max_depth {
+            let (score, best_move, solved) = self.search_root(pos, &moves, complete, depth);
+            if self.aborted {
+                break;
+            }
+            result = Iteration {
+                best_move,
+                score,
+                depth,
+                solved,
+            };
+            if solved {
+                break;
+            }
+            // search the best move first next time round, which makes the pruning more effective
+            if let Some(best) = best_move {
+                moves.retain(|mv| *mv != best);
+                moves.insert(0, best);
+            }
+        }
+        result
+    }
+
+    fn search_root<P>(
+        &mut self,
+        pos: &mut P,
+        moves: &[M],
+        complete: bool,
+        depth: u8,
+    ) -> (i32, Option<M>, bool)
+    where
+        P: SearchPosition<Key = K, Move = M>,
+    {
+        let mover = pos.mover();
+        let mut alpha = -INFINITY;
+        let mut best_move = None;
+        let mut solved = complete;
+        for mv in moves {
+            pos.play(*mv);
+            let (score, child_solved) = self.child_score(pos, mover, depth - 1, alpha, INFINITY);
+            pos.undo();
+            if self.aborted {
+                return (0, None, false);
+            }
+            if !complete && child_solved && score > HEURISTIC_LIMIT {
+                // a proven win doesn't need the moves that were left out
+                return (score, Some(*mv), true);
+            }
+            solved &= child_solved;
+            if score > alpha || best_move.is_none() {
+                alpha = score;
+                best_move = Some(*mv);
+            }
+        }
+        (alpha, best_move, solved)
+    }
+
+    // searches the position after a move by `mover`, returning the score for `mover`
+    fn child_score<P>(
+        &mut self,
+        pos: &mut P,
+        mover: bool,
+        depth: u8,
+        alpha: i32,
+        beta: i32,
+    ) -> (i32, bool)
+    where
+        P: SearchPosition<Key = K, Move = M>,
+    {
+        if pos.mover() == mover {
+            // the same player moves again, so the window isn't flipped
+            self.negamax(pos, depth, alpha, beta)
+        } else {
+            let (score, solved) = self.negamax(pos, depth, -beta, -alpha);
+            (-score, solved)
+        }
+    }
+
+    // returns the score for the player to move and whether it is exact rather than heuristic
+    fn negamax<P>(&mut self, pos: &mut P, depth: u8, mut alpha: i32, beta: i32) -> (i32, bool)
+    where
+        P: SearchPosition<Key = K, Move = M>,
+    {
+        if !self.visit() {
+            return (0, false);
+        }
+        let forced = match pos.expand(depth) {
+            Node::Solved(score) => return (score, true),
+            Node::Horizon(score) => return (score, false),
+            Node::Forced(moves) => Some(moves),
+            Node::Search => None,
+        };
+        let key = pos.key();
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(&key) {
+            if entry.solved || entry.depth >= depth {
+                match entry.bound {
+                    Bound::Exact => return (entry.score, entry.solved),
+                    Bound::Lower if entry.score >= beta => return (entry.score, entry.solved),
+                    Bound::Upper if entry.score <= alpha => return (entry.score, entry.solved),
+                    _ => (),
+                }
+            }
+            table_move = entry.best_move;
+        }
+        let (moves, child_depth, complete) = match forced {
+            // forced moves don't use up depth, so threat sequences are read to the end
+            Some(moves) => (moves, depth, true),
+            None => {
+                let (mut moves, complete) = pos.ordered_moves();
+                if let Some(best) = table_move {
+                    moves.retain(|mv| *mv != best);
+                    moves.insert(0, best);
+                }
+                (moves, depth - 1, complete)
+            }
+        };
+        let mover = pos.mover();
+        let alpha_orig = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        let mut solved = complete;
+        let mut proven_win = false;
+        for mv in moves {
+            pos.play(mv);
+            let (score, child_solved) = self.child_score(pos, mover, child_depth, alpha, beta);
+            pos.undo();
+            if self.aborted {
+                return (0, false);
+            }
+            solved &= child_solved;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                proven_win = child_solved && score > HEURISTIC_LIMIT;
+            }
+            if score > alpha {
+                alpha = score;
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        // moves that were left out could only improve on a loss or a heuristic score
+        let solved = solved || (!complete && proven_win);
+        let bound = if best_score <= alpha_orig {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            key,
+            Entry {
+                score: best_score,
+                bound,
+                depth,
+                solved,
+                best_move,
+            },
+        );
+        (best_score, solved)
+    }
+}
+
+/// Converts an exact score from the first player's point of view into an evaluation,
+/// where `num_moves` have been played in the position searched.
+pub(crate) fn to_evaluation(x_score: i32, num_moves: usize) -> Evaluation {
+    if x_score > HEURISTIC_LIMIT {
+        Evaluation::XWinsIn(((WIN - x_score) as usize - num_moves) as u8)
+    } else if x_score < -HEURISTIC_LIMIT {
+        Evaluation::OWinsIn(((WIN + x_score) as usize - num_moves) as u8)
+    } else {
+        Evaluation::Draw
+    }
+}
@@ -1,5 +1,72 @@
+mod analysis;
 mod bitboards;
 mod board;
+mod cube;
+mod cube_search;
+mod evaluation;
+mod game;
+mod gomoku;
+mod gomoku_search;
+mod infinite;
+mod iterative;
+mod mcts;
+mod menace;
+mod mnk;
+mod mnk_search;
+mod morris;
+mod notakto;
+mod numerical;
+mod order_chaos;
+mod order_chaos_search;
+mod player;
+mod quantum;
+mod retrograde;
+mod rng;
+mod rules;
 mod search;
+mod strength;
+mod symmetry;
+mod tablebase;
+mod tournament;
+mod transposition;
+mod ultimate;
+mod ultimate_search;
+mod wild;
 
+pub use analysis::{MoveAnalysis, MoveOutcome};
+pub use bitboards::{Bitboard, WideBitboard};
 pub use board::Board;
+pub use cube::CubeBoard;
+pub use cube_search::{
+    proof_number_search, CubeSearchResult, CubeSearcher, Proof, ProofNumberResult, CUBE_NODE_LIMIT,
+};
+pub use evaluation::{Evaluation, Outcome, Square};
+pub use game::{play_game, Engine, Game};
+pub use gomoku::{GomokuBoard, Threat, ThreatKind, GOMOKU_SIZE, MAX_GOMOKU_SIZE};
+pub use gomoku_search::{
+    GomokuSearchResult, GomokuSearcher, DEFAULT_BRANCHING, DEFAULT_VCF_DEPTH, GOMOKU_NODE_LIMIT,
+};
+pub use mcts::{Budget, Mcts, MctsResult, MoveStats, DEFAULT_EXPLORATION};
+pub use menace::{Menace, Reinforcement};
+pub use mnk::{Geometry, InvalidGeometryError, MnkBoard};
+pub use mnk_search::{MnkSearchResult, MnkSearcher, DEFAULT_NODE_LIMIT};
+pub use morris::{MorrisBoard, MorrisMove, ADJACENT};
+pub use notakto::{InvalidBoardCountError, NotaktoBoard, NotaktoMove, NotaktoValue};
+pub use numerical::{numerical_search, NumericalBoard, NumericalMove};
+pub use order_chaos::{OrderChaosBoard, OrderChaosMove, Role, ORDER_CHAOS_SIZE};
+pub use order_chaos_search::{OrderChaosSearchResult, OrderChaosSearcher, ORDER_CHAOS_NODE_LIMIT};
+pub use player::{HumanPlayer, StrengthPlayer};
+pub use quantum::{
+    QuantumBoard, QuantumMove, QuantumSearchResult, QuantumSearcher, QUANTUM_NODE_LIMIT,
+};
+pub use rng::Rng;
+pub use rules::Rules;
+pub use search::{Searcher, UnsupportedRulesError};
+pub use strength::Strength;
+pub use symmetry::Transform;
+pub use tablebase::{Tablebase, TablebaseEntry};
+pub use tournament::{play_match, EloEstimate, MatchResult, Record};
+pub use transposition::{Bound, Entry, TableStats, TranspositionTable};
+pub use ultimate::{UltimateBoard, UltimateMove};
+pub use ultimate_search::{UltimateSearchResult, UltimateSearcher, ULTIMATE_NODE_LIMIT};
+pub use wild::{Symbol, WildBoard, WildMove};
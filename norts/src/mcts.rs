@@ -0,0 +1,287 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::time::{Duration, Instant};
+
+use crate::evaluation::Outcome;
+use crate::game::{Engine, Game};
+use crate::rng::Rng;
+
+/// The exploration constant used by `Mcts::new`, the square root of 2 from the original UCT paper.
+pub const DEFAULT_EXPLORATION: f64 = std::f64::consts::SQRT_2;
+
+/// How long `Mcts` searches for each move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Runs this many playouts.
+    Iterations(u32),
+    /// Runs playouts until this much time has passed, finishing at least one.
+    Time(Duration),
+}
+
+/// How a move from the root did in the search.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats<M> {
+    pub mv: M,
+    pub visits: u32,
+    /// The share of playouts through this move won by the player to move, counting draws as half a win.
+    pub win_rate: f64,
+}
+
+/// The result of an `Mcts` search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsResult<M> {
+    /// The most visited move, or `None` if the game is over.
+    pub best_move: Option<M>,
+    /// Every move from the root, most visited first.
+    pub moves: Vec<MoveStats<M>>,
+    pub iterations: u32,
+}
+
+struct Node<M> {
+    mv: Option<M>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // legal moves which don't have a child yet
+    untried: Vec<M>,
+    // the player who played `mv`, as in `Game::current_player`
+    mover: i8,
+    visits: u32,
+    // total result of playouts through this node for `mover`
+    reward: f64,
+}
+
+/// Monte Carlo Tree Search using UCT, which plays any `Game`.
+/// Each iteration walks down the tree picking the child with the best upper confidence bound,
+/// adds one new move to the tree, plays the game out with random moves and feeds the result back up the tree.
+/// No knowledge of the game is needed beyond its rules, so it works on boards too big to search with minimax.
+///
+/// Random choices come from a seeded `Rng`, so a search with an iteration budget is reproducible.
+/// ``` ignore
+/// use norts::{Budget, Mcts, UltimateBoard};
+///
+/// fn main() {
+///     let pos = UltimateBoard::new();
+///     let result = Mcts::new(Budget::Iterations(10_000)).with_seed(7).search(&pos);
+///     for stats in &result.moves {
+///         println!("{}: {} visits, {:.1}%", stats.mv, stats.visits, stats.win_rate * 100.0);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mcts {
+    budget: Budget,
+    exploration: f64,
+    rng: Rng,
+}
+
+impl Mcts {
+    /// Returns a search with the given budget, `DEFAULT_EXPLORATION` and a seed of 0.
+    pub fn new(budget: Budget) -> Mcts {
+        Mcts {
+            budget,
+            exploration: DEFAULT_EXPLORATION,
+            rng: Rng::new(0),
+        }
+    }
+
+    /// Reseeds the random number generator used for playouts.
+    pub fn with_seed(mut self, seed: u64) -> Mcts {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Sets how strongly the search favours rarely visited moves over ones that have done well.
+    pub fn with_exploration(mut self, exploration: f64) -> Mcts {
+        self.exploration = exploration;
+        self
+    }
+
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budget = budget;
+    }
+
+    /// Searches the position, see `Mcts`.
+    pub fn search<G: Game>(&mut self, game: &G) -> MctsResult<G::Move> {
+        let mut nodes = vec![Node {
+            mv: None,
+            parent: None,
+            children: Vec::new(),
+            untried: playable_moves(game),
+            mover: -game.current_player(),
+            visits: 0,
+            reward: 0.0,
+        }];
+        if nodes[0].untried.is_empty() {
+            return MctsResult {
+                best_move: None,
+                moves: Vec::new(),
+                iterations: 0,
+            };
+        }
+        let start = Instant::now();
+        let mut iterations = 0;
+        loop {
+            let done = match self.budget {
+                Budget::Iterations(limit) => iterations >= limit.max(1),
+                Budget::Time(limit) => iterations > 0 && start.elapsed() >= limit,
+            };
+            if done {
+                break;
+            }
+            self.iterate(game, &mut nodes);
+            iterations += 1;
+        }
+        let mut moves: Vec<MoveStats<G::Move>> = nodes[0]
+            .children
+            .iter()
+            .map(|child| {
+                let node = &nodes[*child];
+                MoveStats {
+                    mv: node.mv.unwrap(),
+                    visits: node.visits,
+                    win_rate: node.reward / node.visits as f64,
+                }
+            })
+            .collect();
+        moves.sort_by_key(|stats| std::cmp::Reverse(stats.visits));
+        MctsResult {
+            best_move: moves.first().map(|stats| stats.mv),
+            moves,
+            iterations,
+        }
+    }
+
+    fn iterate<G: Game>(&mut self, game: &G, nodes: &mut Vec<Node<G::Move>>) {
+        let mut state = game.clone();
+        let mut node = 0;
+        // selection
+        while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+            node = self.select_child(nodes, node);
+            state.play_move(nodes[node].mv.unwrap());
+        }
+        // expansion
+        if !nodes[node].untried.is_empty() {
+            let index = self.rng.below(nodes[node].untried.len());
+            let mv = nodes[node].untried.swap_remove(index);
+            let mover = state.current_player();
+            state.play_move(mv);
+            nodes.push(Node {
+                mv: Some(mv),
+                parent: Some(node),
+                children: Vec::new(),
+                untried: playable_moves(&state),
+                mover,
+                visits: 0,
+                reward: 0.0,
+            });
+            let child = nodes.len() - 1;
+            nodes[node].children.push(child);
+            node = child;
+        }
+        // simulation
+        loop {
+            let moves = playable_moves(&state);
+            match self.rng.choose(&moves) {
+                Some(mv) => state.play_move(*mv),
+                None => break,
+            }
+        }
+        // backpropagation, scored for the first player
+        let result = match state.situation() {
+            Outcome::XWon => 1.0,
+            Outcome::OWon => 0.0,
+            _ => 0.5,
+        };
+        let mut current = Some(node);
+        while let Some(index) = current {
+            let node = &mut nodes[index];
+            node.visits += 1;
+            node.reward += if node.mover == 1 {
+                result
+            } else {
+                1.0 - result
+            };
+            current = node.parent;
+        }
+    }
+
+    // the child with the highest upper confidence bound
+    fn select_child<M>(&self, nodes: &[Node<M>], parent: usize) -> usize {
+        let log_visits = (nodes[parent].visits as f64).ln();
+        let bound = |child: usize| {
+            let node = &nodes[child];
+            let visits = node.visits as f64;
+            node.reward / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+        let children = &nodes[parent].children;
+        let mut best = children[0];
+        for child in &children[1..] {
+            if bound(*child) > bound(best) {
+                best = *child;
+            }
+        }
+        best
+    }
+}
+
+impl<G: Game> Engine<G> for Mcts {
+    fn choose_move(&mut self, game: &G) -> Option<G::Move> {
+        self.search(game).best_move
+    }
+}
+
+// legal moves, or none once the game is over for boards that still list moves after a win
+fn playable_moves<G: Game>(game: &G) -> Vec<G::Move> {
+    if game.situation() == Outcome::Ongoing {
+        game.legal_moves()
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::evaluation::Square;
+
+    #[test]
+    fn finds_mate_in_one() {
+        // X takes 2 to win, or O wins at 5
+        let pos = Board::parse_pgn("0314").unwrap();
+        let result = Mcts::new(Budget::Iterations(1000))
+            .with_seed(1)
+            .search(&pos);
+        assert_eq!(result.best_move, Square::new(2));
+        assert_eq!(result.moves[0].win_rate, 1.0);
+        // O to move, with X threatening 2 as well
+        let pos = Board::parse_pgn("03148").unwrap();
+        let result = Mcts::new(Budget::Iterations(1000))
+            .with_seed(2)
+            .search(&pos);
+        assert_eq!(result.best_move, Square::new(5));
+    }
+}
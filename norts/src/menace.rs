@@ -0,0 +1,392 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+use std::slice;
+
+use crate::bitboards::Bitboard;
+use crate::board::Board;
+use crate::evaluation::{Outcome, Square};
+use crate::game::Engine;
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::symmetry::Transform;
+use crate::tournament::{play_match, MatchResult, Record};
+
+const MAGIC: &[u8; 4] = b"NRMB";
+// positions left by symmetry, the most boxes there can be
+const CANONICAL_POSITIONS: usize = 765;
+
+/// Beads added to or taken from every move played, depending on how the game ended for the player who played it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reinforcement {
+    pub win: u16,
+    pub draw: u16,
+    /// Beads taken away, rather than added, after a loss.
+    pub loss: u16,
+}
+
+impl Default for Reinforcement {
+    /// Michie's original scheme: three beads for a win, one for a draw and one taken away for a loss.
+    fn default() -> Self {
+        Reinforcement {
+            win: 3,
+            draw: 1,
+            loss: 1,
+        }
+    }
+}
+
+/// A learning engine after Donald Michie's MENACE, which learnt to play noughts and crosses
+/// with a matchbox of coloured beads for every position.
+/// Each canonical position, see `Bitboard::canonical`, has a box holding beads for every move
+/// which isn't a mirror image of another one, and moves are drawn at random in proportion to their beads.
+/// Boxes start with 4 beads per move on the first move, dropping to 3, 2 and then 1 later in the game.
+/// Once a game ends every move that was played is reinforced according to the result, see `Reinforcement`,
+/// and a box which has run out of beads is refilled the next time it is needed.
+///
+/// Learning happens on its own as the engine plays through the `Engine` trait, whether it finishes the game
+/// itself or is told the opponent did, so `play_game` and `play_match` both train it.
+/// The boxes can't tell which marks will vanish next, so the engine won't play under `Rules::Infinite`.
+/// ``` ignore
+/// use norts::{play_match, Board, Menace, StrengthPlayer};
+///
+/// fn main() {
+///     let mut menace = Menace::new(1);
+///     menace.train_self_play(&Board::new(), 2000);
+///     println!("{}", menace.train_against(&Board::new(), &mut StrengthPlayer::perfect(2), 2000));
+///     menace.set_learning(false);
+///     println!("{}", play_match(&mut menace, &mut StrengthPlayer::perfect(3), &[Board::new()], 100));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Menace {
+    boxes: HashMap<Bitboard, [u16; 9]>,
+    // canonical positions and canonical squares played so far in the current game
+    history: Vec<(Bitboard, u8)>,
+    reinforcement: Reinforcement,
+    learning: bool,
+    rng: Rng,
+}
+
+impl Menace {
+    /// Returns an engine which knows nothing yet and learns with the default `Reinforcement`.
+    pub fn new(seed: u64) -> Menace {
+        Menace {
+            boxes: HashMap::new(),
+            history: Vec::new(),
+            reinforcement: Reinforcement::default(),
+            learning: true,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn with_reinforcement(mut self, reinforcement: Reinforcement) -> Menace {
+        self.reinforcement = reinforcement;
+        self
+    }
+
+    pub fn reinforcement(&self) -> Reinforcement {
+        self.reinforcement
+    }
+
+    pub fn is_learning(&self) -> bool {
+        self.learning
+    }
+
+    /// Turns learning on or off, so that a trained engine can be measured without changing it.
+    pub fn set_learning(&mut self, learning: bool) {
+        self.learning = learning;
+        self.history.clear();
+    }
+
+    /// Returns the number of boxes, which is at most the number of canonical positions.
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// Empties every box, forgetting everything learnt so far.
+    pub fn clear(&mut self) {
+        self.boxes.clear();
+        self.history.clear();
+    }
+
+    /// Returns the beads on each square of `pos`, or `None` if the engine hasn't played from that position yet.
+    /// Squares which mirror another square hold no beads of their own.
+    pub fn beads(&self, pos: &Bitboard) -> Option<[u16; 9]> {
+        let (canonical, transform) = pos.canonical();
+        let counts = self.boxes.get(&canonical)?;
+        let mut beads = [0; 9];
+        for (square, count) in beads.iter_mut().enumerate() {
+            *count = counts[transform.apply_square(square as u8) as usize];
+        }
+        Some(beads)
+    }
+
+    /// Draws a move for the position, opening a new box if needed, and remembers it for `Menace::learn`.
+    /// Returns `None` if the game has already ended.
+    pub fn choose(&mut self, pos: &Bitboard) -> Option<Square> {
+        if pos.x_won() || pos.o_won() || pos.is_draw() {
+            return None;
+        }
+        // a position with fewer moves than the last one means a new game was started without finishing the old one
+        if let Some((last, _)) = self.history.last() {
+            if last.num_moves() >= pos.num_moves() {
+                self.history.clear();
+            }
+        }
+        let (canonical, transform) = pos.canonical();
+        let counts = self
+            .boxes
+            .entry(canonical)
+            .or_insert_with(|| new_box(&canonical));
+        if counts.iter().all(|count| *count == 0) {
+            *counts = new_box(&canonical);
+        }
+        let total: usize = counts.iter().map(|count| *count as usize).sum();
+        let mut bead = self.rng.below(total);
+        let mut square = 0;
+        while bead >= counts[square] as usize {
+            bead -= counts[square] as usize;
+            square += 1;
+        }
+        if self.learning {
+            self.history.push((canonical, square as u8));
+        }
+        Square::new(transform.invert_square(square as u8))
+    }
+
+    /// Reinforces every move remembered from the game that ended in `end`, then forgets them.
+    /// Nothing is learnt if the game hasn't ended.
+    pub fn learn(&mut self, end: &Board) {
+        let x_points = match end.situation() {
+            Outcome::XWon => 1,
+            Outcome::OWon => -1,
+            Outcome::Draw => 0,
+            Outcome::Ongoing => {
+                self.history.clear();
+                return;
+            }
+        };
+        for (pos, square) in self.history.drain(..) {
+            let points = if pos.current_player() {
+                x_points
+            } else {
+                -x_points
+            };
+            let count = &mut self.boxes.get_mut(&pos).unwrap()[square as usize];
+            *count = match points {
+                1 => count.saturating_add(self.reinforcement.win),
+                0 => count.saturating_add(self.reinforcement.draw),
+                _ => count.saturating_sub(self.reinforcement.loss),
+            };
+        }
+    }
+
+    /// Plays `games` games against itself from `start`, learning from both sides,
+    /// and returns the results for the player moving first.
+    /// Nothing is played under `Rules::Infinite`.
+    pub fn train_self_play(&mut self, start: &Board, games: u32) -> Record {
+        let mut record = Record::default();
+        if start.rules() == Rules::Infinite {
+            return record;
+        }
+        for _ in 0..games {
+            let mut game = start.clone();
+            while let Some(square) = self.choose_move(&game) {
+                game.play(i8::from(square)).unwrap();
+            }
+            match (game.situation(), start.bitboard.current_player()) {
+                (Outcome::Draw, _) | (Outcome::Ongoing, _) => record.draws += 1,
+                (Outcome::XWon, true) | (Outcome::OWon, false) => record.wins += 1,
+                _ => record.losses += 1,
+            }
+        }
+        record
+    }
+
+    /// Plays `games` games against `opponent` from `start`, taking turns to move first,
+    /// and returns the match from this engine's point of view. Use `StrengthPlayer::perfect` to learn from the solver.
+    /// Nothing is played under `Rules::Infinite`.
+    pub fn train_against(
+        &mut self,
+        start: &Board,
+        opponent: &mut dyn Engine<Board>,
+        games: u32,
+    ) -> MatchResult {
+        if start.rules() == Rules::Infinite {
+            return MatchResult::default();
+        }
+        let learning = self.learning;
+        self.set_learning(true);
+        let result = play_match(self, opponent, slice::from_ref(start), games);
+        self.set_learning(learning);
+        result
+    }
+
+    /// Writes every box to `writer` so they can be reloaded with `Menace::load`.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.boxes.len() as u32).to_le_bytes())?;
+        for (pos, counts) in &self.boxes {
+            writer.write_all(&pos.x_bitboard.to_le_bytes())?;
+            writer.write_all(&pos.o_bitboard.to_le_bytes())?;
+            for count in counts {
+                writer.write_all(&count.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replaces the boxes with ones previously written with `Menace::save`.
+    pub fn load<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a norts MENACE file"));
+        }
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        // the length comes from the file, so it is only trusted as far as the number of positions
+        let mut boxes = HashMap::with_capacity(len.min(CANONICAL_POSITIONS));
+        for _ in 0..len {
+            let mut buf = [0; 22];
+            reader.read_exact(&mut buf)?;
+            let pos = Bitboard {
+                x_bitboard: u16::from_le_bytes([buf[0], buf[1]]),
+                o_bitboard: u16::from_le_bytes([buf[2], buf[3]]),
+            };
+            if pos.canonical().0 != pos {
+                return Err(invalid_data("position in MENACE file is not canonical"));
+            }
+            let mut counts = [0; 9];
+            for (square, count) in counts.iter_mut().enumerate() {
+                *count = u16::from_le_bytes([buf[4 + 2 * square], buf[5 + 2 * square]]);
+                if *count > 0 && !pos.is_legal(square as u8) {
+                    return Err(invalid_data("beads on an occupied square in MENACE file"));
+                }
+            }
+            boxes.insert(pos, counts);
+        }
+        self.boxes = boxes;
+        self.history.clear();
+        Ok(())
+    }
+}
+
+impl Engine<Board> for Menace {
+    /// Returns `None` once the game has ended, and under `Rules::Infinite`.
+    fn choose_move(&mut self, game: &Board) -> Option<Square> {
+        if !game.is_in_play() || game.rules() == Rules::Infinite {
+            return None;
+        }
+        let square = self.choose(&game.bitboard)?;
+        if self.learning {
+            let mut after = game.clone();
+            after.play(i8::from(square)).unwrap();
+            if !after.is_in_play() {
+                self.learn(&after);
+            }
+        }
+        Some(square)
+    }
+
+    fn opponent_moved(&mut self, game: &Board, _mv: Square) {
+        if self.learning && !game.is_in_play() {
+            self.learn(game);
+        }
+    }
+}
+
+// fills a box with beads for each legal square, leaving out squares which are mirror images of a lower square
+fn new_box(pos: &Bitboard) -> [u16; 9] {
+    let beads = 4u16.saturating_sub(pos.num_moves() as u16 / 2).max(1);
+    let symmetries: Vec<Transform> = Transform::ALL
+        .iter()
+        .copied()
+        .filter(|transform| pos.transform(*transform) == *pos)
+        .collect();
+    let mut counts = [0; 9];
+    for square in 0..9 {
+        if pos.is_legal(square)
+            && symmetries
+                .iter()
+                .all(|transform| transform.apply_square(square) >= square)
+        {
+            counts[square as usize] = beads;
+        }
+    }
+    counts
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let mut menace = Menace::new(1);
+        menace.train_self_play(&Board::new(), 200);
+        let mut bytes = Vec::new();
+        menace.save(&mut bytes).unwrap();
+        let mut loaded = Menace::new(2);
+        loaded.load(&mut &bytes[..]).unwrap();
+        assert_eq!(loaded.boxes, menace.boxes);
+        assert_eq!(
+            loaded.beads(&Bitboard::new()),
+            menace.beads(&Bitboard::new())
+        );
+    }
+
+    #[test]
+    fn load_rejects_bad_files() {
+        let mut menace = Menace::new(1);
+        assert!(menace.load(&mut &b"NRTT"[..]).is_err());
+        // a huge length with no boxes after it
+        assert!(menace.load(&mut &b"NRMB\xff\xff\xff\xff"[..]).is_err());
+    }
+
+    #[test]
+    fn refuses_infinite_games() {
+        let start = Board::with_rules(Rules::Infinite);
+        for seed in 0..20 {
+            let mut menace = Menace::new(seed);
+            assert_eq!(menace.train_self_play(&start, 300), Record::default());
+            assert!(menace.is_empty());
+            assert_eq!(menace.choose_move(&start), None);
+        }
+        let mut menace = Menace::new(1);
+        let drawn = Board::parse_pgn("402681357").unwrap();
+        assert_eq!(drawn.situation(), Outcome::Draw);
+        assert_eq!(menace.choose_move(&drawn), None);
+    }
+}
@@ -0,0 +1,532 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::bitboards::WideBitboard;
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+use crate::mnk_search::MnkSearcher;
+
+#[derive(Debug, Clone)]
+pub struct InvalidGeometryError;
+
+impl fmt::Display for InvalidGeometryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Board must have between 1 and {} squares and k must fit on the board.",
+            WideBitboard::MAX_SQUARES
+        )
+    }
+}
+
+/// The shape of an m,n,k game: a `width` by `height` board where `k` in a row wins.
+/// Every winning line is precomputed as a bitboard mask, along with the lines through each square
+/// so that only the lines touching the last move need checking.
+///
+/// Squares are numbered left to right, top to bottom, so square `row * width + col`.
+#[derive(Debug, Clone)]
+pub struct Geometry {
+    width: usize,
+    height: usize,
+    k: usize,
+    lines: Vec<WideBitboard>,
+    lines_through: Vec<Vec<usize>>,
+    // squares sorted from the centre outwards, central squares are usually the strongest
+    centre_order: Vec<usize>,
+}
+
+impl Geometry {
+    pub fn new(width: usize, height: usize, k: usize) -> Result<Geometry, InvalidGeometryError> {
+        if width == 0
+            || height == 0
+            || width * height > WideBitboard::MAX_SQUARES
+            || k == 0
+            || k > width.max(height)
+        {
+            return Err(InvalidGeometryError);
+        }
+        let directions: &[(isize, isize)] = if k == 1 {
+            // every direction gives the same single square line
+            &[(1, 0)]
+        } else {
+            &[(1, 0), (0, 1), (1, 1), (-1, 1)]
+        };
+        let mut lines = Vec::new();
+        let mut lines_through = vec![Vec::new(); width * height];
+        for row in 0..height as isize {
+            for col in 0..width as isize {
+                for (dx, dy) in directions {
+                    let end_col = col + dx * (k as isize - 1);
+                    let end_row = row + dy * (k as isize - 1);
+                    if end_col < 0 || end_col >= width as isize || end_row >= height as isize {
+                        continue;
+                    }
+                    let mut line = WideBitboard::new();
+                    for i in 0..k as isize {
+                        let square = ((row + dy * i) * width as isize + col + dx * i) as usize;
+                        line.set(square);
+                        lines_through[square].push(lines.len());
+                    }
+                    lines.push(line);
+                }
+            }
+        }
+        let mut centre_order: Vec<usize> = (0..width * height).collect();
+        centre_order.sort_by_key(|square| {
+            let col = (2 * (square % width)) as isize - (width as isize - 1);
+            let row = (2 * (square / width)) as isize - (height as isize - 1);
+            col * col + row * row
+        });
+        Ok(Geometry {
+            width,
+            height,
+            k,
+            lines,
+            lines_through,
+            centre_order,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of marks in a row needed to win.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    pub fn num_squares(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Every winning line on the board.
+    pub fn lines(&self) -> &[WideBitboard] {
+        &self.lines
+    }
+
+    /// Indices into `Geometry::lines` of the lines passing through `square`.
+    pub fn lines_through(&self, square: usize) -> &[usize] {
+        &self.lines_through[square]
+    }
+
+    /// Every square, ordered from the centre of the board outwards.
+    pub fn centre_order(&self) -> &[usize] {
+        &self.centre_order
+    }
+}
+
+/// A board for m,n,k games such as 4x4 with 4 in a row, or 5x5 with 4 in a row.
+/// Works like `Board` but squares are `usize` as there can be more than 9 of them.
+/// ``` ignore
+/// use norts::MnkBoard;
+///
+/// fn main() {
+///     let mut pos = MnkBoard::new(4, 4, 4).unwrap();
+///     pos.play(5).unwrap();
+///     pos.show();
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+///
+/// Boards made with `MnkBoard::new_gravity` play Connect-Four style, where marks drop to the lowest empty square
+/// of a column. On those boards moves are columns rather than squares: `play`, `is_valid_move`, `legal_moves`,
+/// `best_move` and the PGN all use column numbers, counted from 0 on the left.
+/// ``` ignore
+/// use norts::MnkBoard;
+///
+/// fn main() {
+///     // Connect Four
+///     let mut pos = MnkBoard::new_gravity(7, 6, 4).unwrap();
+///     pos.play(3).unwrap();
+///     pos.play(3).unwrap();
+///     pos.show();
+///     println!("{}", pos.pgn()); // 3,3
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MnkBoard {
+    geometry: Arc<Geometry>,
+    gravity: bool,
+    pub x_bitboard: WideBitboard,
+    pub o_bitboard: WideBitboard,
+    // squares filled, in order
+    history: Vec<usize>,
+}
+
+impl MnkBoard {
+    /// Returns an empty `width` by `height` board where `k` in a row wins.
+    pub fn new(width: usize, height: usize, k: usize) -> Result<MnkBoard, InvalidGeometryError> {
+        Ok(MnkBoard::with_geometry(Arc::new(Geometry::new(
+            width, height, k,
+        )?)))
+    }
+
+    /// Returns an empty gravity board, see `MnkBoard`.
+    pub fn new_gravity(
+        width: usize,
+        height: usize,
+        k: usize,
+    ) -> Result<MnkBoard, InvalidGeometryError> {
+        Ok(MnkBoard::with_geometry_gravity(Arc::new(Geometry::new(
+            width, height, k,
+        )?)))
+    }
+
+    /// Returns an empty board sharing an existing geometry, which avoids recomputing the lines.
+    pub fn with_geometry(geometry: Arc<Geometry>) -> MnkBoard {
+        MnkBoard {
+            geometry,
+            gravity: false,
+            x_bitboard: WideBitboard::new(),
+            o_bitboard: WideBitboard::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns an empty gravity board sharing an existing geometry.
+    pub fn with_geometry_gravity(geometry: Arc<Geometry>) -> MnkBoard {
+        MnkBoard {
+            gravity: true,
+            ..MnkBoard::with_geometry(geometry)
+        }
+    }
+
+    pub fn geometry(&self) -> &Arc<Geometry> {
+        &self.geometry
+    }
+
+    /// Returns true if marks drop to the bottom of their column.
+    pub fn has_gravity(&self) -> bool {
+        self.gravity
+    }
+
+    /// Returns a board which picks up from the specified PGN string.
+    /// As squares can be more than one digit, moves in the PGN are separated by commas, e.g. "5,10,6".
+    pub fn parse_pgn(
+        width: usize,
+        height: usize,
+        k: usize,
+        pgn: &str,
+    ) -> Result<MnkBoard, InvalidPgnError> {
+        let pos = MnkBoard::new(width, height, k).map_err(|_| InvalidPgnError)?;
+        pos.with_pgn(pgn)
+    }
+
+    /// Returns a gravity board which picks up from the specified PGN string of comma separated columns.
+    pub fn parse_pgn_gravity(
+        width: usize,
+        height: usize,
+        k: usize,
+        pgn: &str,
+    ) -> Result<MnkBoard, InvalidPgnError> {
+        let pos = MnkBoard::new_gravity(width, height, k).map_err(|_| InvalidPgnError)?;
+        pos.with_pgn(pgn)
+    }
+
+    fn with_pgn(mut self, pgn: &str) -> Result<MnkBoard, InvalidPgnError> {
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let mv = part.parse().map_err(|_| InvalidPgnError)?;
+            self.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the moves played so far, see `MnkBoard::parse_pgn`.
+    /// Gravity boards give columns, see `MnkBoard::parse_pgn_gravity`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|square| self.to_move(*square).to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Returns the squares filled so far, in order, even on gravity boards.
+    pub fn history(&self) -> &[usize] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.x_to_move() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn x_to_move(&self) -> bool {
+        self.history.len() % 2 == 0
+    }
+
+    /// Checks a square, or a column on gravity boards, can be played.
+    pub fn is_valid_move(&self, mv: usize) -> bool {
+        self.to_square(mv).is_some() && self.is_in_play()
+    }
+
+    // the square a move fills, which on gravity boards is the lowest empty square of the column
+    fn to_square(&self, mv: usize) -> Option<usize> {
+        if !self.gravity {
+            return (mv < self.geometry.num_squares() && self.is_empty(mv)).then_some(mv);
+        }
+        if mv >= self.geometry.width {
+            return None;
+        }
+        (0..self.geometry.height)
+            .rev()
+            .map(|row| row * self.geometry.width + mv)
+            .find(|square| self.is_empty(*square))
+    }
+
+    // the inverse of `to_square`
+    fn to_move(&self, square: usize) -> usize {
+        if self.gravity {
+            square % self.geometry.width
+        } else {
+            square
+        }
+    }
+
+    /// Returns true if a mark could be placed on the square,
+    /// so it is empty and on gravity boards also has nothing empty below it.
+    #[inline(always)]
+    pub(crate) fn is_playable(&self, square: usize) -> bool {
+        self.is_empty(square)
+            && (!self.gravity
+                || square + self.geometry.width >= self.geometry.num_squares()
+                || !self.is_empty(square + self.geometry.width))
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self, square: usize) -> bool {
+        !self.x_bitboard.get(square) && !self.o_bitboard.get(square)
+    }
+
+    /// Returns every legal move in square order, or column order on gravity boards.
+    pub fn legal_moves(&self) -> Vec<usize> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        let num_moves = if self.gravity {
+            self.geometry.width
+        } else {
+            self.geometry.num_squares()
+        };
+        (0..num_moves)
+            .filter(|mv| self.to_square(*mv).is_some())
+            .collect()
+    }
+
+    /// Plays a move to the certain square, see `Geometry` for how squares are numbered.
+    /// On gravity boards the move is a column and the mark drops to the lowest empty square in it.
+    pub fn play(&mut self, mv: usize) -> Result<bool, IllegalMoveError> {
+        match self.to_square(mv) {
+            Some(square) if self.is_in_play() => {
+                self.play_unchecked(square);
+                Ok(true)
+            }
+            _ => Err(IllegalMoveError),
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        if self.history.is_empty() {
+            return Err(NoMoveToUndoError);
+        }
+        self.undo_unchecked();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn play_unchecked(&mut self, square: usize) {
+        if self.x_to_move() {
+            self.x_bitboard.set(square);
+        } else {
+            self.o_bitboard.set(square);
+        }
+        self.history.push(square);
+    }
+
+    #[inline(always)]
+    pub(crate) fn undo_unchecked(&mut self) {
+        let square = self.history.pop().unwrap();
+        self.x_bitboard.clear_square(square);
+        self.o_bitboard.clear_square(square);
+    }
+
+    /// Returns true if the last move completed a line.
+    /// Only the last move needs checking as the game stops as soon as someone wins.
+    #[inline(always)]
+    pub(crate) fn last_move_won(&self) -> bool {
+        let square = match self.history.last() {
+            Some(square) => *square,
+            None => return false,
+        };
+        let marks = if self.x_bitboard.get(square) {
+            &self.x_bitboard
+        } else {
+            &self.o_bitboard
+        };
+        self.geometry
+            .lines_through(square)
+            .iter()
+            .any(|line| marks.contains_all(&self.geometry.lines[*line]))
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        self.history.len() == self.geometry.num_squares()
+    }
+
+    /// Returns whether X or O has won, the game is drawn or it is still ongoing.
+    pub fn situation(&self) -> Outcome {
+        if self.last_move_won() {
+            if self.x_to_move() {
+                Outcome::OWon
+            } else {
+                Outcome::XWon
+            }
+        } else if self.is_full() {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        !(self.last_move_won() || self.is_full())
+    }
+
+    /// Returns the best move found by a default `MnkSearcher`,
+    /// which solves small boards and falls back to a depth limited search on larger ones.
+    /// This is a column on gravity boards.
+    pub fn best_move(&mut self) -> Result<usize, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut MnkSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut MnkSearcher,
+    ) -> Result<usize, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            let square = searcher.search(self).best_move.unwrap();
+            Ok(self.to_move(square))
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        let width = (self.geometry.num_squares() - 1).to_string().len();
+        println!("Board:");
+        print!("{}", self);
+        if self.gravity {
+            println!("Columns:");
+            for col in 0..self.geometry.width {
+                print!(" {:>width$} ", col, width = width);
+            }
+            println!();
+            return;
+        }
+        println!("Squares:");
+        for row in 0..self.geometry.height {
+            for col in 0..self.geometry.width {
+                print!(
+                    " {:>width$} ",
+                    row * self.geometry.width + col,
+                    width = width
+                );
+            }
+            println!();
+        }
+    }
+}
+
+impl fmt::Display for MnkBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..self.geometry.height {
+            for col in 0..self.geometry.width {
+                let square = row * self.geometry.width + col;
+                if self.x_bitboard.get(square) {
+                    write!(f, " X ")?;
+                } else if self.o_bitboard.get(square) {
+                    write!(f, " O ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_columns() {
+        let mut pos = MnkBoard::new_gravity(7, 6, 4).unwrap();
+        assert_eq!(pos.legal_moves(), (0..7).collect::<Vec<_>>());
+        assert!(!pos.is_valid_move(7));
+        // marks drop to the bottom of the column and stack up
+        pos.play(3).unwrap();
+        pos.play(3).unwrap();
+        assert_eq!(pos.history(), &[38, 31]);
+        assert_eq!(pos.pgn(), "3,3");
+
+        // a full column can't be played, while the others still can
+        let mut pos = MnkBoard::parse_pgn_gravity(7, 6, 4, "0,0,0,0,0,0").unwrap();
+        assert!(pos.is_in_play());
+        assert!(!pos.is_valid_move(0));
+        assert!(pos.play(0).is_err());
+        assert_eq!(pos.legal_moves(), (1..7).collect::<Vec<_>>());
+        assert!(MnkBoard::parse_pgn_gravity(7, 6, 4, "0,0,0,0,0,0,0").is_err());
+
+        pos.undo_move().unwrap();
+        assert!(pos.is_valid_move(0));
+    }
+}
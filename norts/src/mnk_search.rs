@@ -0,0 +1,214 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::bitboards::WideBitboard;
+use crate::evaluation::Evaluation;
+use crate::iterative::{
+    to_evaluation, IterativeSearch, Node, SearchPosition, HEURISTIC_LIMIT, WIN,
+};
+use crate::mnk::MnkBoard;
+
+/// Nodes searched by a default `MnkSearcher` before it settles for its last completed depth.
+pub const DEFAULT_NODE_LIMIT: u64 = 1_000_000;
+
+/// The result of searching an `MnkBoard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MnkSearchResult {
+    /// The square to play, even on gravity boards.
+    pub best_move: Option<usize>,
+    /// Score from the point of view of the player to move.
+    /// Wins are ±1,000,000,000 less the length of the game, anything else is a heuristic estimate.
+    pub score: i32,
+    /// The exact value of the position if the search was able to solve it.
+    pub evaluation: Option<Evaluation>,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search for `MnkBoard`s with a transposition table.
+/// Each iteration searches one move deeper until the position is solved,
+/// the maximum depth is reached or the node budget runs out,
+/// in which case the last completed iteration is used.
+/// Positions past the depth limit are scored by counting lines that are still open for each player.
+pub struct MnkSearcher {
+    search: IterativeSearch<(WideBitboard, WideBitboard), usize>,
+    // width, height, k and gravity of the boards in the table, whose scores mean nothing for any other game
+    game: Option<(usize, usize, usize, bool)>,
+}
+
+impl MnkSearcher {
+    /// Returns a searcher with no depth limit and a budget of `DEFAULT_NODE_LIMIT` nodes per search.
+    pub fn new() -> MnkSearcher {
+        MnkSearcher {
+            search: IterativeSearch::new(DEFAULT_NODE_LIMIT),
+            game: None,
+        }
+    }
+
+    /// Limits how many moves ahead the search looks.
+    pub fn with_max_depth(mut self, depth: u8) -> MnkSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit.
+    pub fn with_node_limit(mut self, nodes: u64) -> MnkSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+
+    /// Searches the position, see `MnkSearcher`.
+    /// The table is cleared if the board is a different size or shape, or has different gravity, to the last one.
+    pub fn search(&mut self, pos: &mut MnkBoard) -> MnkSearchResult {
+        self.search.reset();
+        let geometry = pos.geometry();
+        let game = (
+            geometry.width(),
+            geometry.height(),
+            geometry.k(),
+            pos.has_gravity(),
+        );
+        if self.game != Some(game) {
+            self.search.clear();
+            self.game = Some(game);
+        }
+        let remaining = geometry.num_squares() - pos.num_moves();
+        if !pos.is_in_play() {
+            return MnkSearchResult {
+                best_move: None,
+                score: 0,
+                evaluation: None,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+        let iteration = self.search.search(pos, remaining);
+        let x_score = if pos.x_to_move() {
+            iteration.score
+        } else {
+            -iteration.score
+        };
+        MnkSearchResult {
+            best_move: iteration.best_move,
+            score: iteration.score,
+            evaluation: iteration
+                .solved
+                .then(|| to_evaluation(x_score, pos.num_moves())),
+            depth: iteration.depth,
+            nodes: self.search.nodes(),
+        }
+    }
+}
+
+impl Default for MnkSearcher {
+    fn default() -> Self {
+        MnkSearcher::new()
+    }
+}
+
+impl SearchPosition for MnkBoard {
+    type Move = usize;
+    type Key = (WideBitboard, WideBitboard);
+
+    fn key(&self) -> Self::Key {
+        (self.x_bitboard, self.o_bitboard)
+    }
+
+    fn mover(&self) -> bool {
+        self.x_to_move()
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<usize> {
+        if self.last_move_won() {
+            // the player who just moved won
+            Node::Solved(-(WIN - self.num_moves() as i32))
+        } else if self.is_full() {
+            Node::Solved(0)
+        } else if depth == 0 {
+            Node::Horizon(heuristic(self))
+        } else {
+            Node::Search
+        }
+    }
+
+    fn ordered_moves(&mut self) -> (Vec<usize>, bool) {
+        let moves = self
+            .geometry()
+            .centre_order()
+            .iter()
+            .copied()
+            .filter(|square| self.is_playable(*square))
+            .collect();
+        (moves, true)
+    }
+
+    fn play(&mut self, square: usize) {
+        self.play_unchecked(square);
+    }
+
+    fn undo(&mut self) {
+        self.undo_unchecked();
+    }
+}
+
+// counts the lines each player can still complete, weighting fuller lines more heavily
+fn heuristic(pos: &MnkBoard) -> i32 {
+    let mut score: i64 = 0;
+    for line in pos.geometry().lines() {
+        let x = pos.x_bitboard.intersection(line).count_ones();
+        let o = pos.o_bitboard.intersection(line).count_ones();
+        if o == 0 && x > 0 {
+            score += 1 << (2 * x.min(10));
+        } else if x == 0 && o > 0 {
+            score -= 1 << (2 * o.min(10));
+        }
+    }
+    let score = score.clamp(-HEURISTIC_LIMIT as i64, HEURISTIC_LIMIT as i64) as i32;
+    if pos.x_to_move() {
+        score
+    } else {
+        -score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn three_in_a_row_on_3x3_is_a_draw() {
+        let mut pos = MnkBoard::new(3, 3, 3).unwrap();
+        let result = MnkSearcher::new().search(&mut pos);
+        assert_eq!(result.evaluation, Some(Evaluation::Draw));
+        assert_eq!(result.score, 0);
+        // X wins after "01" on a normal board
+        let mut pos = MnkBoard::parse_pgn(3, 3, 3, "0,1").unwrap();
+        let result = MnkSearcher::new().search(&mut pos);
+        assert_eq!(result.evaluation, Some(Evaluation::XWinsIn(5)));
+        assert!([3, 4, 6].contains(&result.best_move.unwrap()));
+    }
+}
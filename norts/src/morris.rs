@@ -0,0 +1,415 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::OnceLock;
+
+use crate::bitboards::{has_line, Bitboard};
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::{Evaluation, Outcome};
+use crate::retrograde::{solve, Value};
+
+/// The squares each square is joined to, as a bitboard mask.
+/// Marks slide along the lines of the board, so every square is joined to its neighbours along rows and columns,
+/// and the centre is also joined to the corners along the diagonals.
+pub const ADJACENT: [u16; 9] = [
+    0b000_011_010,
+    0b000_010_101,
+    0b000_110_010,
+    0b001_010_001,
+    0b111_101_111,
+    0b100_010_100,
+    0b010_011_000,
+    0b101_010_000,
+    0b010_110_000,
+];
+
+/// Number of marks each player places before marks start sliding.
+const MARKS_PER_PLAYER: u8 = 3;
+/// A position coming up this many times in a game is a draw.
+const REPETITIONS_FOR_DRAW: usize = 3;
+
+static SOLUTION: OnceLock<HashMap<MorrisState, Value>> = OnceLock::new();
+
+/// A move in Three Men's Morris, placing a new mark during the first six moves and sliding one afterwards.
+/// Squares are numbered like squares on a normal `Board`.
+/// Placements are written as the square and slides as "from-to", such as "0-1".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MorrisMove {
+    Place(u8),
+    Slide { from: u8, to: u8 },
+}
+
+impl fmt::Display for MorrisMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MorrisMove::Place(square) => write!(f, "{}", square),
+            MorrisMove::Slide { from, to } => write!(f, "{}-{}", from, to),
+        }
+    }
+}
+
+// the marks and whose turn it is, which is all that matters for the rest of the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MorrisState {
+    marks: Bitboard,
+    x_to_move: bool,
+}
+
+impl MorrisState {
+    fn new() -> MorrisState {
+        MorrisState {
+            marks: Bitboard::new(),
+            x_to_move: true,
+        }
+    }
+
+    fn is_placement(&self) -> bool {
+        self.marks.num_moves() < 2 * MARKS_PER_PLAYER
+    }
+
+    fn legal_moves(&self) -> Vec<MorrisMove> {
+        let empty = !(self.marks.x_bitboard | self.marks.o_bitboard);
+        if self.is_placement() {
+            return (0..9)
+                .filter(|square| empty & (1 << square) != 0)
+                .map(MorrisMove::Place)
+                .collect();
+        }
+        let own = if self.x_to_move {
+            self.marks.x_bitboard
+        } else {
+            self.marks.o_bitboard
+        };
+        let mut moves = Vec::new();
+        for from in (0..9).filter(|square| own & (1 << square) != 0) {
+            let targets = ADJACENT[from as usize] & empty;
+            for to in (0..9).filter(|square| targets & (1 << square) != 0) {
+                moves.push(MorrisMove::Slide { from, to });
+            }
+        }
+        moves
+    }
+
+    fn play(&self, mv: MorrisMove) -> MorrisState {
+        let mut marks = self.marks;
+        let own = if self.x_to_move {
+            &mut marks.x_bitboard
+        } else {
+            &mut marks.o_bitboard
+        };
+        match mv {
+            MorrisMove::Place(square) => *own |= 1 << square,
+            MorrisMove::Slide { from, to } => *own = *own & !(1 << from) | 1 << to,
+        }
+        MorrisState {
+            marks,
+            x_to_move: !self.x_to_move,
+        }
+    }
+
+    // the game is over and the player to move has lost,
+    // either because the player who just moved made a line or because they can't move
+    fn is_lost(&self) -> bool {
+        let line = if self.x_to_move {
+            has_line(self.marks.o_bitboard)
+        } else {
+            has_line(self.marks.x_bitboard)
+        };
+        line || self.legal_moves().is_empty()
+    }
+
+    fn children(&self) -> Vec<MorrisState> {
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| self.play(mv))
+            .collect()
+    }
+}
+
+fn solution() -> &'static HashMap<MorrisState, Value> {
+    SOLUTION.get_or_init(|| {
+        solve(MorrisState::new(), MorrisState::children, |state| {
+            state.is_lost().then_some(Value::Loss(0))
+        })
+    })
+}
+
+/// Three Men's Morris. Players take turns placing three marks each as in tic-tac-toe,
+/// then take turns sliding one of their marks to an empty square joined to it, see `ADJACENT`.
+/// Making a line wins, a player who can't move loses and a position coming up for the third time is a draw.
+///
+/// The game is solved by retrograde analysis the first time it is needed.
+/// ``` ignore
+/// use norts::MorrisBoard;
+///
+/// fn main() {
+///     let mut pos = MorrisBoard::parse_pgn("4,0,8,2,1,7").unwrap();
+///     pos.show();
+///     println!("{}, best move {}", pos.evaluation().unwrap(), pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MorrisBoard {
+    pub bitboard: Bitboard,
+    history: Vec<MorrisMove>,
+}
+
+impl MorrisBoard {
+    pub fn new() -> MorrisBoard {
+        MorrisBoard {
+            bitboard: Bitboard::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// a comma separated list of moves written as in `MorrisMove`, such as "4,0,8,2,1,7,1-5".
+    pub fn parse_pgn(pgn: &str) -> Result<MorrisBoard, InvalidPgnError> {
+        let mut pos = MorrisBoard::new();
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let mv = match part.split_once('-') {
+                Some((from, to)) => MorrisMove::Slide {
+                    from: from.parse().map_err(|_| InvalidPgnError)?,
+                    to: to.parse().map_err(|_| InvalidPgnError)?,
+                },
+                None => MorrisMove::Place(part.parse().map_err(|_| InvalidPgnError)?),
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `MorrisBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn history(&self) -> &[MorrisMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.history.len() % 2 == 0 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    fn state(&self) -> MorrisState {
+        MorrisState {
+            marks: self.bitboard,
+            x_to_move: self.current_player() == 1,
+        }
+    }
+
+    /// Returns true while marks are still being placed rather than slid.
+    pub fn is_placement_phase(&self) -> bool {
+        self.state().is_placement()
+    }
+
+    pub fn is_valid_move(&self, mv: MorrisMove) -> bool {
+        self.legal_moves().contains(&mv)
+    }
+
+    /// Returns every legal move, placements in square order during the placement phase
+    /// and slides ordered by the square moved from afterwards.
+    pub fn legal_moves(&self) -> Vec<MorrisMove> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        self.state().legal_moves()
+    }
+
+    pub fn play(&mut self, mv: MorrisMove) -> Result<bool, IllegalMoveError> {
+        if !self.is_valid_move(mv) {
+            return Err(IllegalMoveError);
+        }
+        self.bitboard = self.state().play(mv).marks;
+        self.history.push(mv);
+        Ok(true)
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        let mv = self.history.pop().ok_or(NoMoveToUndoError)?;
+        // whoever is to move now made the move being undone
+        let own = if self.current_player() == 1 {
+            &mut self.bitboard.x_bitboard
+        } else {
+            &mut self.bitboard.o_bitboard
+        };
+        match mv {
+            MorrisMove::Place(square) => *own &= !(1 << square),
+            MorrisMove::Slide { from, to } => *own = *own & !(1 << to) | 1 << from,
+        }
+        Ok(())
+    }
+
+    // the number of times the current position has come up in the game, counting this one
+    fn repetitions(&self) -> usize {
+        let current = self.state();
+        let mut state = MorrisState::new();
+        let mut count = usize::from(state == current);
+        for mv in &self.history {
+            state = state.play(*mv);
+            count += usize::from(state == current);
+        }
+        count
+    }
+
+    /// Returns whether X or O has won, the game is drawn by repetition or it is still ongoing.
+    pub fn situation(&self) -> Outcome {
+        let state = self.state();
+        if state.is_lost() {
+            if state.x_to_move {
+                Outcome::OWon
+            } else {
+                Outcome::XWon
+            }
+        } else if self.repetitions() >= REPETITIONS_FOR_DRAW {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.situation() == Outcome::Ongoing
+    }
+
+    /// Returns the value of the position with perfect play.
+    /// Positions that neither player can win are draws, as they can be repeated until the game is drawn.
+    pub fn evaluation(&self) -> Result<Evaluation, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let state = self.state();
+        Ok(solution()[&state].to_evaluation(state.x_to_move))
+    }
+
+    /// Returns every move which keeps the best result for the player to move,
+    /// winning as quickly or losing as slowly as possible.
+    pub fn best_moves(&self) -> Result<Vec<MorrisMove>, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let solution = solution();
+        let state = self.state();
+        let values: Vec<(MorrisMove, Value)> = state
+            .legal_moves()
+            .into_iter()
+            .map(|mv| (mv, solution[&state.play(mv)].for_mover()))
+            .collect();
+        let best = values.iter().map(|(_, value)| value.rank()).max().unwrap();
+        Ok(values
+            .into_iter()
+            .filter(|(_, value)| value.rank() == best)
+            .map(|(mv, _)| mv)
+            .collect())
+    }
+
+    /// Returns the first of the best moves, see `MorrisBoard::best_moves`.
+    pub fn best_move(&self) -> Result<MorrisMove, PositionAlreadyConcludedError> {
+        Ok(self.best_moves()?[0])
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("*-----------------------*");
+        println!(" Board:         Squares:");
+        for (row, line) in self.to_string().lines().enumerate() {
+            println!(
+                "{}       {}  {}  {}  ",
+                line,
+                row * 3,
+                row * 3 + 1,
+                row * 3 + 2
+            );
+        }
+        if !self.is_placement_phase() {
+            println!(" Marks slide to joined squares");
+        }
+        println!("*-----------------------*");
+    }
+}
+
+impl Default for MorrisBoard {
+    fn default() -> Self {
+        MorrisBoard::new()
+    }
+}
+
+impl fmt::Display for MorrisBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..3 {
+            for col in 0..3 {
+                let square = row * 3 + col;
+                if self.bitboard.x_bitboard & (1 << square) != 0 {
+                    write!(f, " X ")?;
+                } else if self.bitboard.o_bitboard & (1 << square) != 0 {
+                    write!(f, " O ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centre_wins_for_the_first_player() {
+        let mut pos = MorrisBoard::new();
+        assert_eq!(pos.evaluation().unwrap(), Evaluation::XWinsIn(9));
+        assert_eq!(pos.best_moves().unwrap(), vec![MorrisMove::Place(4)]);
+        while pos.is_in_play() {
+            let mv = pos.best_move().unwrap();
+            pos.play(mv).unwrap();
+        }
+        assert_eq!(pos.situation(), Outcome::XWon);
+        assert_eq!(pos.num_moves(), 9);
+    }
+}
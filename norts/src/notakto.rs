@@ -0,0 +1,504 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::ops::Mul;
+
+use crate::bitboards::{has_line, Bitboard};
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+
+#[derive(Debug, Clone)]
+pub struct InvalidBoardCountError;
+
+impl fmt::Display for InvalidBoardCountError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Notakto needs at least one board.")
+    }
+}
+
+/// An element of the Notakto misère quotient found by Plambeck and Whitehead,
+/// the monoid generated by a, b, c and d with the relations
+/// a² = 1, b³ = b, b²c = c, c³ = ac², b²d = d, cd = ad and d² = c².
+///
+/// Every position on a single board has a value in it, the value of several boards is the product of their values,
+/// and the player to move loses with best play exactly when the value is one of a, b², bc or c².
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotaktoValue {
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+}
+
+const ONE: NotaktoValue = NotaktoValue::new(0, 0, 0, 0);
+const A: NotaktoValue = NotaktoValue::new(1, 0, 0, 0);
+const B: NotaktoValue = NotaktoValue::new(0, 1, 0, 0);
+const AB: NotaktoValue = NotaktoValue::new(1, 1, 0, 0);
+const C: NotaktoValue = NotaktoValue::new(0, 0, 1, 0);
+const CC: NotaktoValue = NotaktoValue::new(0, 0, 2, 0);
+const D: NotaktoValue = NotaktoValue::new(0, 0, 0, 1);
+const AD: NotaktoValue = NotaktoValue::new(1, 0, 0, 1);
+
+// the value of every live board in canonical form, see `Bitboard::canonical`.
+// Boards are written as bitboards, so the digits run from square 8 down to square 0
+const BOARD_VALUES: [(u16, NotaktoValue); 46] = [
+    (0b000_000_000, C),
+    (0b000_000_001, ONE),
+    (0b000_000_010, ONE),
+    (0b000_000_011, D),
+    (0b000_000_101, B),
+    (0b000_001_010, A),
+    (0b000_001_011, B),
+    (0b000_001_100, B),
+    (0b000_001_101, A),
+    (0b000_001_110, AD),
+    (0b000_010_000, CC),
+    (0b000_010_001, B),
+    (0b000_010_010, B),
+    (0b000_010_011, AB),
+    (0b000_010_101, A),
+    (0b000_011_010, AB),
+    (0b000_011_011, A),
+    (0b000_011_100, A),
+    (0b000_011_101, B),
+    (0b000_011_110, B),
+    (0b000_101_000, A),
+    (0b000_101_001, AD),
+    (0b000_101_010, B),
+    (0b000_101_011, A),
+    (0b000_101_101, B),
+    (0b001_000_100, A),
+    (0b001_000_101, AB),
+    (0b001_000_110, AD),
+    (0b001_001_110, AB),
+    (0b001_100_001, A),
+    (0b001_100_010, ONE),
+    (0b001_100_011, B),
+    (0b001_100_101, B),
+    (0b001_100_110, A),
+    (0b001_101_010, AB),
+    (0b001_101_100, A),
+    (0b001_101_110, B),
+    (0b001_110_001, B),
+    (0b001_110_010, B),
+    (0b001_110_011, A),
+    (0b010_101_010, A),
+    (0b010_101_011, B),
+    (0b010_101_101, A),
+    (0b011_100_101, A),
+    (0b011_101_110, A),
+    (0b101_000_101, A),
+];
+
+impl NotaktoValue {
+    /// The value of a finished game, or of boards which are all dead.
+    pub const IDENTITY: NotaktoValue = ONE;
+
+    const fn new(a: u8, b: u8, c: u8, d: u8) -> NotaktoValue {
+        NotaktoValue { a, b, c, d }
+    }
+
+    /// Returns the value of a single board of X marks, dead boards being the identity.
+    pub fn of_board(board: u16) -> NotaktoValue {
+        if has_line(board) {
+            return ONE;
+        }
+        let mut pos = Bitboard::new();
+        pos.x_bitboard = board;
+        let canonical = pos.canonical().0.x_bitboard;
+        let index = BOARD_VALUES
+            .binary_search_by_key(&canonical, |(board, _)| *board)
+            .expect("every live canonical board has a value");
+        BOARD_VALUES[index].1
+    }
+
+    /// Returns true if the player to move loses with best play, a P-position in combinatorial game theory.
+    pub fn is_p_position(self) -> bool {
+        self == A
+            || self == NotaktoValue::new(0, 2, 0, 0)
+            || self == NotaktoValue::new(0, 1, 1, 0)
+            || self == CC
+    }
+
+    // rewrites the exponents into the normal form a^(0..2) b^(0..3) c^(0..3) d^(0..2)
+    fn reduce(a: u32, mut b: u32, mut c: u32, mut d: u32) -> NotaktoValue {
+        let mut a = a;
+        // d² = c²
+        c += 2 * (d / 2);
+        d %= 2;
+        // cd = ad
+        if d == 1 {
+            a += c;
+            c = 0;
+        }
+        // c³ = ac²
+        if c > 2 {
+            a += c - 2;
+            c = 2;
+        }
+        // b³ = b
+        if b > 2 {
+            b = 2 - b % 2;
+        }
+        // b²c = c and b²d = d
+        if b == 2 && (c > 0 || d > 0) {
+            b = 0;
+        }
+        NotaktoValue::new((a % 2) as u8, b as u8, c as u8, d as u8)
+    }
+}
+
+impl Mul for NotaktoValue {
+    type Output = NotaktoValue;
+
+    fn mul(self, other: NotaktoValue) -> NotaktoValue {
+        NotaktoValue::reduce(
+            (self.a + other.a) as u32,
+            (self.b + other.b) as u32,
+            (self.c + other.c) as u32,
+            (self.d + other.d) as u32,
+        )
+    }
+}
+
+impl fmt::Display for NotaktoValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if *self == ONE {
+            return write!(f, "1");
+        }
+        for (letter, power) in [("a", self.a), ("b", self.b), ("c", self.c), ("d", self.d)] {
+            match power {
+                0 => (),
+                1 => write!(f, "{}", letter)?,
+                _ => write!(f, "{}²", letter)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A move in Notakto: a square on one of the boards, numbered like squares on a normal `Board`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NotaktoMove {
+    pub board: usize,
+    pub square: u8,
+}
+
+impl fmt::Display for NotaktoMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.board, self.square)
+    }
+}
+
+/// Notakto, tic-tac-toe where both players play X on any number of boards.
+/// A board is dead as soon as it has a line of three on it, and whoever completes a line on the last live board loses.
+/// The game is solved by the misère quotient, see `NotaktoValue`, so `best_move` is instant for any number of boards.
+///
+/// There are no draws, `Outcome::XWon` means the first player won and `Outcome::OWon` the second.
+/// ``` ignore
+/// use norts::NotaktoBoard;
+///
+/// fn main() {
+///     let mut pos = NotaktoBoard::parse_pgn(3, "04,14").unwrap();
+///     pos.show();
+///     println!("Value: {}", pos.value());
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NotaktoBoard {
+    /// The X marks on each board.
+    pub boards: Vec<u16>,
+    history: Vec<NotaktoMove>,
+}
+
+impl NotaktoBoard {
+    /// Returns `num_boards` empty boards.
+    pub fn new(num_boards: usize) -> Result<NotaktoBoard, InvalidBoardCountError> {
+        if num_boards == 0 {
+            return Err(InvalidBoardCountError);
+        }
+        Ok(NotaktoBoard {
+            boards: vec![0; num_boards],
+            history: Vec::new(),
+        })
+    }
+
+    /// Returns a board which picks up from the specified PGN string.
+    /// Moves are separated by commas and written as the board number followed by the square,
+    /// so "04,14" is the centre of the first board then the centre of the second.
+    pub fn parse_pgn(num_boards: usize, pgn: &str) -> Result<NotaktoBoard, InvalidPgnError> {
+        let mut pos = NotaktoBoard::new(num_boards).map_err(|_| InvalidPgnError)?;
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            if part.len() < 2 || !part.is_ascii() {
+                return Err(InvalidPgnError);
+            }
+            let (board, square) = part.split_at(part.len() - 1);
+            let mv = NotaktoMove {
+                board: board.parse().map_err(|_| InvalidPgnError)?,
+                square: square.parse().map_err(|_| InvalidPgnError)?,
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `NotaktoBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn history(&self) -> &[NotaktoMove] {
+        &self.history
+    }
+
+    pub fn num_boards(&self) -> usize {
+        self.boards.len()
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if the first player is to move and -1 if the second player is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.history.len() % 2 == 0 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Returns true if the board has a line on it, so no more moves can be played on it.
+    pub fn is_dead(&self, board: usize) -> bool {
+        has_line(self.boards[board])
+    }
+
+    /// Returns the indices of the boards which are still in play.
+    pub fn live_boards(&self) -> Vec<usize> {
+        (0..self.boards.len())
+            .filter(|board| !self.is_dead(*board))
+            .collect()
+    }
+
+    pub fn is_valid_move(&self, mv: NotaktoMove) -> bool {
+        mv.board < self.boards.len()
+            && mv.square < 9
+            && !self.is_dead(mv.board)
+            && self.boards[mv.board] & (1 << mv.square) == 0
+    }
+
+    /// Returns every legal move, board by board.
+    pub fn legal_moves(&self) -> Vec<NotaktoMove> {
+        let mut moves = Vec::new();
+        for board in self.live_boards() {
+            for square in 0..9 {
+                if self.boards[board] & (1 << square) == 0 {
+                    moves.push(NotaktoMove { board, square });
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn play(&mut self, mv: NotaktoMove) -> Result<bool, IllegalMoveError> {
+        if self.is_valid_move(mv) {
+            self.boards[mv.board] |= 1 << mv.square;
+            self.history.push(mv);
+            Ok(true)
+        } else {
+            Err(IllegalMoveError)
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        let mv = self.history.pop().ok_or(NoMoveToUndoError)?;
+        self.boards[mv.board] &= !(1 << mv.square);
+        Ok(())
+    }
+
+    /// Returns `Outcome::XWon` if the first player won, `Outcome::OWon` if the second player won,
+    /// or `Outcome::Ongoing`. Notakto cannot be drawn.
+    pub fn situation(&self) -> Outcome {
+        if self.is_in_play() {
+            Outcome::Ongoing
+        } else if self.current_player() == 1 {
+            // the second player killed the last board
+            Outcome::XWon
+        } else {
+            Outcome::OWon
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.boards.iter().any(|board| !has_line(*board))
+    }
+
+    /// Returns the value of the position, the product of the values of the live boards.
+    pub fn value(&self) -> NotaktoValue {
+        self.boards
+            .iter()
+            .fold(NotaktoValue::IDENTITY, |value, board| {
+                value * NotaktoValue::of_board(*board)
+            })
+    }
+
+    /// Returns every move which leaves the opponent lost with best play.
+    pub fn winning_moves(&self) -> Vec<NotaktoMove> {
+        self.legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                let value = self.boards.iter().enumerate().fold(
+                    NotaktoValue::IDENTITY,
+                    |value, (index, board)| {
+                        let board = if index == mv.board {
+                            board | 1 << mv.square
+                        } else {
+                            *board
+                        };
+                        value * NotaktoValue::of_board(board)
+                    },
+                );
+                value.is_p_position()
+            })
+            .collect()
+    }
+
+    /// Returns a winning move if there is one. Otherwise the position is lost,
+    /// so it returns a move that keeps the game going as long as possible in the hope of a mistake.
+    pub fn best_move(&self) -> Result<NotaktoMove, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        if let Some(mv) = self.winning_moves().first() {
+            return Ok(*mv);
+        }
+        let moves = self.legal_moves();
+        Ok(*moves
+            .iter()
+            .find(|mv| !has_line(self.boards[mv.board] | 1 << mv.square))
+            .unwrap_or(&moves[0]))
+    }
+
+    /// Displays a visual representation of the boards to the standard output.
+    pub fn show(&self) {
+        for board in 0..self.boards.len() {
+            let label = if self.is_dead(board) {
+                format!("{} (dead)", board)
+            } else {
+                board.to_string()
+            };
+            print!(" {:<9}", label);
+        }
+        println!();
+        print!("{}", self);
+    }
+}
+
+impl fmt::Display for NotaktoBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..3 {
+            for (index, board) in self.boards.iter().enumerate() {
+                if index > 0 {
+                    write!(f, "|")?;
+                }
+                for col in 0..3 {
+                    if board & (1 << (row * 3 + col)) != 0 {
+                        write!(f, " X ")?;
+                    } else {
+                        write!(f, " . ")?;
+                    }
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::rng::Rng;
+
+    // whether the player to move wins, found by trying every move
+    fn mover_wins(boards: &mut Vec<u16>, seen: &mut HashMap<Vec<u16>, bool>) -> bool {
+        if let Some(wins) = seen.get(boards) {
+            return *wins;
+        }
+        // whoever killed the last board lost
+        let num_boards = boards.len();
+        let wins = boards.iter().all(|board| has_line(*board))
+            || (0..num_boards).any(|board| {
+                !has_line(boards[board])
+                    && (0..9).any(|square| {
+                        if boards[board] & (1 << square) != 0 {
+                            return false;
+                        }
+                        boards[board] |= 1 << square;
+                        let reply_wins = mover_wins(boards, seen);
+                        boards[board] &= !(1 << square);
+                        !reply_wins
+                    })
+            });
+        seen.insert(boards.clone(), wins);
+        wins
+    }
+
+    #[test]
+    fn values_match_brute_force() {
+        let mut seen = HashMap::new();
+        for board in 0..1 << 9 {
+            let pos = NotaktoBoard {
+                boards: vec![board],
+                ..NotaktoBoard::new(1).unwrap()
+            };
+            let wins = mover_wins(&mut pos.boards.clone(), &mut seen);
+            assert_eq!(pos.value().is_p_position(), !wins, "{:09b}", board);
+        }
+        let mut rng = Rng::new(3);
+        for _ in 0..800 {
+            let boards: Vec<u16> = (0..2).map(|_| rng.below(1 << 9) as u16).collect();
+            let pos = NotaktoBoard {
+                boards: boards.clone(),
+                ..NotaktoBoard::new(2).unwrap()
+            };
+            let wins = mover_wins(&mut boards.clone(), &mut seen);
+            assert_eq!(pos.value().is_p_position(), !wins, "{:?}", boards);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::{Mutex, OnceLock};
+
+use crate::bitboards::LINES;
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::{Evaluation, Outcome};
+use crate::transposition::Bound;
+
+type Table = HashMap<u64, (i8, Bound, Option<NumericalMove>)>;
+
+// shared by every search, so positions solved once are never searched again
+static NUMERICAL_TABLE: OnceLock<Mutex<Table>> = OnceLock::new();
+
+/// A move in Numerical tic-tac-toe: a square, numbered like squares on a normal `Board`, and the number placed on it.
+/// Written as the square followed by the number, so "45" puts a 5 in the centre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NumericalMove {
+    pub square: u8,
+    pub number: u8,
+}
+
+impl fmt::Display for NumericalMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.square, self.number)
+    }
+}
+
+/// Numerical tic-tac-toe, Ronald Graham's 15 game. The first player places the odd numbers 1 to 9
+/// and the second the even numbers 2 to 8, each number once, and whoever completes a line of three
+/// numbers adding up to 15 wins, whichever player placed the other two.
+///
+/// Evaluations and outcomes use X for the first player and O for the second.
+/// ``` ignore
+/// use norts::NumericalBoard;
+///
+/// fn main() {
+///     let mut pos = NumericalBoard::parse_pgn("4106").unwrap();
+///     pos.show();
+///     println!("{}, best move {}", pos.evaluation().unwrap(), pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NumericalBoard {
+    /// The number on each square, 0 if the square is empty.
+    pub squares: [u8; 9],
+    // bit n is set once the number n has been placed
+    used: u16,
+    history: Vec<NumericalMove>,
+}
+
+impl NumericalBoard {
+    pub fn new() -> NumericalBoard {
+        NumericalBoard {
+            squares: [0; 9],
+            used: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// two digits per move for the square then the number, see `NumericalMove`.
+    pub fn parse_pgn(pgn: &str) -> Result<NumericalBoard, InvalidPgnError> {
+        let digits: Vec<u8> = pgn
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8).ok_or(InvalidPgnError))
+            .collect::<Result<_, _>>()?;
+        if digits.len() % 2 != 0 {
+            return Err(InvalidPgnError);
+        }
+        let mut pos = NumericalBoard::new();
+        for pair in digits.chunks(2) {
+            let mv = NumericalMove {
+                square: pair[0],
+                number: pair[1],
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `NumericalBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history.iter().map(|mv| mv.to_string()).collect()
+    }
+
+    pub fn history(&self) -> &[NumericalMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if the first player, with the odd numbers, is to move and -1 if the second player is.
+    pub fn current_player(&self) -> i8 {
+        if self.first_to_move() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[inline(always)]
+    fn first_to_move(&self) -> bool {
+        self.history.len() % 2 == 0
+    }
+
+    /// Returns the numbers the player to move has left to place.
+    pub fn available_numbers(&self) -> Vec<u8> {
+        let parity = if self.first_to_move() { 1 } else { 0 };
+        (1..=9)
+            .filter(|number| number % 2 == parity && self.used & (1 << number) == 0)
+            .collect()
+    }
+
+    pub fn is_valid_move(&self, mv: NumericalMove) -> bool {
+        mv.square < 9
+            && self.squares[mv.square as usize] == 0
+            && self.available_numbers().contains(&mv.number)
+            && self.is_in_play()
+    }
+
+    /// Returns every legal move, square by square.
+    pub fn legal_moves(&self) -> Vec<NumericalMove> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        let numbers = self.available_numbers();
+        (0..9)
+            .filter(|square| self.squares[*square as usize] == 0)
+            .flat_map(|square| {
+                numbers.iter().map(move |number| NumericalMove {
+                    square,
+                    number: *number,
+                })
+            })
+            .collect()
+    }
+
+    pub fn play(&mut self, mv: NumericalMove) -> Result<bool, IllegalMoveError> {
+        if self.is_valid_move(mv) {
+            self.play_unchecked(mv);
+            Ok(true)
+        } else {
+            Err(IllegalMoveError)
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        if self.history.is_empty() {
+            return Err(NoMoveToUndoError);
+        }
+        self.undo_unchecked();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn play_unchecked(&mut self, mv: NumericalMove) {
+        self.squares[mv.square as usize] = mv.number;
+        self.used |= 1 << mv.number;
+        self.history.push(mv);
+    }
+
+    #[inline(always)]
+    fn undo_unchecked(&mut self) {
+        let mv = self.history.pop().unwrap();
+        self.squares[mv.square as usize] = 0;
+        self.used &= !(1 << mv.number);
+    }
+
+    /// Returns true if some line is full and adds up to 15.
+    pub fn has_fifteen(&self) -> bool {
+        LINES.iter().any(|line| {
+            let (mut filled, mut sum) = (0, 0);
+            for square in (0..9).filter(|square| line & (1 << square) != 0) {
+                if self.squares[square] != 0 {
+                    filled += 1;
+                    sum += self.squares[square];
+                }
+            }
+            filled == 3 && sum == 15
+        })
+    }
+
+    /// Returns `Outcome::XWon` if the first player made 15, `Outcome::OWon` if the second player did,
+    /// `Outcome::Draw` if the board filled up without a 15, or `Outcome::Ongoing`.
+    pub fn situation(&self) -> Outcome {
+        if self.has_fifteen() {
+            // the player who just moved made 15
+            if self.first_to_move() {
+                Outcome::OWon
+            } else {
+                Outcome::XWon
+            }
+        } else if self.history.len() == 9 {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.situation() == Outcome::Ongoing
+    }
+
+    /// Solves the position, where X wins for the first player and O wins for the second.
+    pub fn evaluation(&mut self) -> Result<Evaluation, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let (score, _) = numerical_search(self, -100, 100);
+        Ok(Evaluation::from_score(score, self.num_moves() as u8))
+    }
+
+    /// Returns the best move found by `numerical_search`.
+    pub fn best_move(&mut self) -> Result<NumericalMove, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        Ok(numerical_search(self, -100, 100).1.unwrap())
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("*-----------------------*");
+        println!(" Board:         Squares:");
+        for (row, line) in self.to_string().lines().enumerate() {
+            println!(
+                "{}       {}  {}  {}  ",
+                line,
+                row * 3,
+                row * 3 + 1,
+                row * 3 + 2
+            );
+        }
+        let numbers: Vec<String> = self
+            .available_numbers()
+            .iter()
+            .map(|number| number.to_string())
+            .collect();
+        println!(" To place: {}", numbers.join(" "));
+        println!("*-----------------------*");
+    }
+
+    // every square as a hex digit, which identifies the position for the transposition table
+    fn key(&self) -> u64 {
+        self.squares
+            .iter()
+            .fold(0, |key, number| key << 4 | *number as u64)
+    }
+}
+
+impl Default for NumericalBoard {
+    fn default() -> Self {
+        NumericalBoard::new()
+    }
+}
+
+impl fmt::Display for NumericalBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..3 {
+            for col in 0..3 {
+                match self.squares[row * 3 + col] {
+                    0 => write!(f, " . ")?,
+                    number => write!(f, " {} ", number)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves a Numerical tic-tac-toe position with alpha-beta pruning and a transposition table,
+/// the counterpart of `search::search` for `NumericalBoard`.
+/// Scores are `100 - game length` for a first player win, `-100 + game length` for a second player win
+/// and 0 for a draw, returned along with the best move or `None` if the game is over.
+/// The table is kept between calls, so only the first search from a position does any work.
+pub fn numerical_search(
+    pos: &mut NumericalBoard,
+    alpha: i8,
+    beta: i8,
+) -> (i8, Option<NumericalMove>) {
+    let table = NUMERICAL_TABLE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut table = table.lock().unwrap();
+    alpha_beta(pos, alpha, beta, &mut table)
+}
+
+fn alpha_beta(
+    pos: &mut NumericalBoard,
+    mut alpha: i8,
+    mut beta: i8,
+    table: &mut Table,
+) -> (i8, Option<NumericalMove>) {
+    match pos.situation() {
+        Outcome::XWon => return (100 - pos.num_moves() as i8, None),
+        Outcome::OWon => return (-100 + pos.num_moves() as i8, None),
+        Outcome::Draw => return (0, None),
+        Outcome::Ongoing => (),
+    }
+    let key = pos.key();
+    if let Some((eval, bound, best_move)) = table.get(&key) {
+        match bound {
+            Bound::Exact => return (*eval, *best_move),
+            Bound::Lower if *eval >= beta => return (*eval, *best_move),
+            Bound::Upper if *eval <= alpha => return (*eval, *best_move),
+            _ => (),
+        }
+    }
+    let (alpha_orig, beta_orig) = (alpha, beta);
+    // the first player wants to maximise the eval and the second to minimise it
+    let maximising = pos.first_to_move();
+    let mut best_eval = if maximising {
+        i8::MIN + 10
+    } else {
+        i8::MAX - 10
+    };
+    let mut best_move = None;
+    for mv in pos.legal_moves() {
+        pos.play_unchecked(mv);
+        let eval = alpha_beta(pos, alpha, beta, table).0;
+        pos.undo_unchecked();
+        if (maximising && eval > best_eval) || (!maximising && eval < best_eval) {
+            best_eval = eval;
+            best_move = Some(mv);
+        }
+        if maximising {
+            alpha = alpha.max(eval);
+        } else {
+            beta = beta.min(eval);
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+    // results outside the window are only bounds on the true value
+    let bound = if best_eval <= alpha_orig {
+        Bound::Upper
+    } else if best_eval >= beta_orig {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    table.insert(key, (best_eval, bound, best_move));
+    (best_eval, best_move)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_player_wins() {
+        let mut board = NumericalBoard::new();
+        assert_eq!(board.evaluation().unwrap(), Evaluation::XWinsIn(9));
+        // following the best moves keeps the win
+        while board.is_in_play() {
+            let mv = board.best_move().unwrap();
+            board.play(mv).unwrap();
+        }
+        assert_eq!(board.situation(), Outcome::XWon);
+    }
+}
@@ -0,0 +1,346 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::Arc;
+
+use crate::bitboards::WideBitboard;
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+use crate::mnk::Geometry;
+use crate::order_chaos_search::OrderChaosSearcher;
+use crate::wild::Symbol;
+
+/// Width and height of the board.
+pub const ORDER_CHAOS_SIZE: usize = 6;
+/// Number of the same symbol in a row Order needs.
+const FIVE: usize = 5;
+
+/// The two sides in Order and Chaos, which have different goals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Moves first and wins by making five in a row of either symbol.
+    Order,
+    /// Wins if the board fills up without five in a row.
+    Chaos,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Order => write!(f, "Order"),
+            Role::Chaos => write!(f, "Chaos"),
+        }
+    }
+}
+
+/// A move in Order and Chaos: a square, numbered as in `Geometry`, and the symbol placed on it.
+/// Written as the square followed by the symbol, such as "14x".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OrderChaosMove {
+    pub square: usize,
+    pub symbol: Symbol,
+}
+
+impl fmt::Display for OrderChaosMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.square, self.symbol)
+    }
+}
+
+/// Order and Chaos on a 6x6 board. Both players may place either symbol:
+/// Order wins as soon as there are five of the same symbol in a row, whoever placed them,
+/// and Chaos wins if the board fills up first.
+///
+/// Outcomes and evaluations use X for Order and O for Chaos.
+/// The game is too big to solve, so it is played with a depth limited search, see `OrderChaosSearcher`.
+/// ``` ignore
+/// use norts::OrderChaosBoard;
+///
+/// fn main() {
+///     let mut pos = OrderChaosBoard::parse_pgn("14x,15o").unwrap();
+///     pos.show();
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrderChaosBoard {
+    geometry: Arc<Geometry>,
+    pub x_bitboard: WideBitboard,
+    pub o_bitboard: WideBitboard,
+    history: Vec<OrderChaosMove>,
+}
+
+impl OrderChaosBoard {
+    pub fn new() -> OrderChaosBoard {
+        OrderChaosBoard {
+            geometry: Arc::new(Geometry::new(ORDER_CHAOS_SIZE, ORDER_CHAOS_SIZE, FIVE).unwrap()),
+            x_bitboard: WideBitboard::new(),
+            o_bitboard: WideBitboard::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// The board's lines of five, shared with the m,n,k games.
+    pub fn geometry(&self) -> &Arc<Geometry> {
+        &self.geometry
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// a comma separated list of moves written as in `OrderChaosMove`, such as "14x,15o".
+    pub fn parse_pgn(pgn: &str) -> Result<OrderChaosBoard, InvalidPgnError> {
+        let mut pos = OrderChaosBoard::new();
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            if part.len() < 2 || !part.is_ascii() {
+                return Err(InvalidPgnError);
+            }
+            let (square, symbol) = part.split_at(part.len() - 1);
+            let symbol = match symbol {
+                "x" | "X" => Symbol::X,
+                "o" | "O" => Symbol::O,
+                _ => return Err(InvalidPgnError),
+            };
+            let mv = OrderChaosMove {
+                square: square.parse().map_err(|_| InvalidPgnError)?,
+                symbol,
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `OrderChaosBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn history(&self) -> &[OrderChaosMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns the side to move, Order moving first.
+    pub fn current_role(&self) -> Role {
+        if self.order_to_move() {
+            Role::Order
+        } else {
+            Role::Chaos
+        }
+    }
+
+    /// Returns 1 if Order is to move and -1 if Chaos is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.order_to_move() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn order_to_move(&self) -> bool {
+        self.history.len() % 2 == 0
+    }
+
+    #[inline(always)]
+    pub fn is_empty(&self, square: usize) -> bool {
+        !self.x_bitboard.get(square) && !self.o_bitboard.get(square)
+    }
+
+    pub fn is_valid_move(&self, mv: OrderChaosMove) -> bool {
+        mv.square < self.geometry.num_squares() && self.is_empty(mv.square) && self.is_in_play()
+    }
+
+    /// Returns every legal move, both symbols on each empty square in square order.
+    pub fn legal_moves(&self) -> Vec<OrderChaosMove> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        (0..self.geometry.num_squares())
+            .filter(|square| self.is_empty(*square))
+            .flat_map(|square| {
+                [Symbol::X, Symbol::O].map(|symbol| OrderChaosMove { square, symbol })
+            })
+            .collect()
+    }
+
+    pub fn play(&mut self, mv: OrderChaosMove) -> Result<bool, IllegalMoveError> {
+        if self.is_valid_move(mv) {
+            self.play_unchecked(mv);
+            Ok(true)
+        } else {
+            Err(IllegalMoveError)
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        if self.history.is_empty() {
+            return Err(NoMoveToUndoError);
+        }
+        self.undo_unchecked();
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub(crate) fn play_unchecked(&mut self, mv: OrderChaosMove) {
+        match mv.symbol {
+            Symbol::X => self.x_bitboard.set(mv.square),
+            Symbol::O => self.o_bitboard.set(mv.square),
+        }
+        self.history.push(mv);
+    }
+
+    #[inline(always)]
+    pub(crate) fn undo_unchecked(&mut self) {
+        let mv = self.history.pop().unwrap();
+        self.x_bitboard.clear_square(mv.square);
+        self.o_bitboard.clear_square(mv.square);
+    }
+
+    /// Returns true if the last move made five in a row.
+    /// Only the last move needs checking as the game stops as soon as there is one.
+    #[inline(always)]
+    pub(crate) fn last_move_won(&self) -> bool {
+        let mv = match self.history.last() {
+            Some(mv) => *mv,
+            None => return false,
+        };
+        let marks = match mv.symbol {
+            Symbol::X => &self.x_bitboard,
+            Symbol::O => &self.o_bitboard,
+        };
+        self.geometry
+            .lines_through(mv.square)
+            .iter()
+            .any(|line| marks.contains_all(&self.geometry.lines()[*line]))
+    }
+
+    #[inline(always)]
+    pub(crate) fn is_full(&self) -> bool {
+        self.history.len() == self.geometry.num_squares()
+    }
+
+    /// Returns true if some line of five has no mix of symbols, so Order could still complete it.
+    /// Once every line is blocked Chaos is certain to win, although the game goes on until the board is full.
+    pub fn order_can_win(&self) -> bool {
+        self.geometry
+            .lines()
+            .iter()
+            .any(|line| !(self.x_bitboard.intersects(line) && self.o_bitboard.intersects(line)))
+    }
+
+    /// Returns `Outcome::XWon` if Order has made five in a row, `Outcome::OWon` if Chaos filled the board,
+    /// or `Outcome::Ongoing`.
+    pub fn situation(&self) -> Outcome {
+        if self.last_move_won() {
+            Outcome::XWon
+        } else if self.is_full() {
+            Outcome::OWon
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns the side which won, or `None` if the game is still going.
+    pub fn winner(&self) -> Option<Role> {
+        match self.situation() {
+            Outcome::XWon => Some(Role::Order),
+            Outcome::OWon => Some(Role::Chaos),
+            _ => None,
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        !(self.last_move_won() || self.is_full())
+    }
+
+    /// Returns the best move found by a default `OrderChaosSearcher`.
+    pub fn best_move(&mut self) -> Result<OrderChaosMove, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut OrderChaosSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut OrderChaosSearcher,
+    ) -> Result<OrderChaosMove, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            Ok(searcher.search(self).best_move.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("Board:");
+        print!("{}", self);
+        println!("Squares:");
+        for row in 0..ORDER_CHAOS_SIZE {
+            for col in 0..ORDER_CHAOS_SIZE {
+                print!(" {:>2} ", row * ORDER_CHAOS_SIZE + col);
+            }
+            println!();
+        }
+        println!("{} to move", self.current_role());
+    }
+}
+
+impl Default for OrderChaosBoard {
+    fn default() -> Self {
+        OrderChaosBoard::new()
+    }
+}
+
+impl fmt::Display for OrderChaosBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..ORDER_CHAOS_SIZE {
+            for col in 0..ORDER_CHAOS_SIZE {
+                let square = row * ORDER_CHAOS_SIZE + col;
+                if self.x_bitboard.get(square) {
+                    write!(f, " X ")?;
+                } else if self.o_bitboard.get(square) {
+                    write!(f, " O ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
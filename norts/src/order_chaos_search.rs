@@ -0,0 +1,219 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::bitboards::WideBitboard;
+use crate::evaluation::Evaluation;
+use crate::iterative::{
+    to_evaluation, IterativeSearch, Node, SearchPosition, HEURISTIC_LIMIT, WIN,
+};
+use crate::order_chaos::{OrderChaosBoard, OrderChaosMove};
+use crate::wild::Symbol;
+
+/// Nodes searched by a default `OrderChaosSearcher` before it settles for its last completed depth.
+pub const ORDER_CHAOS_NODE_LIMIT: u64 = 1_000_000;
+
+/// The result of searching an `OrderChaosBoard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderChaosSearchResult {
+    pub best_move: Option<OrderChaosMove>,
+    /// Score from the point of view of the player to move.
+    /// Wins are ±1,000,000,000 less the length of the game, anything else is a heuristic estimate.
+    pub score: i32,
+    /// The exact value of the position if the search was able to solve it,
+    /// with X standing for Order and O for Chaos.
+    pub evaluation: Option<Evaluation>,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search for `OrderChaosBoard`s with a transposition table,
+/// working the same way as `MnkSearcher`.
+/// Positions where every line of five holds both symbols are scored as wins for Chaos straight away.
+/// Positions past the depth limit are scored by counting the lines still open for Order,
+/// weighting lines with more of one symbol more heavily.
+/// ``` ignore
+/// use norts::{OrderChaosBoard, OrderChaosSearcher};
+///
+/// fn main() {
+///     let mut pos = OrderChaosBoard::parse_pgn("14x,15o").unwrap();
+///     let result = OrderChaosSearcher::new().with_max_depth(3).search(&mut pos);
+///     println!("{} scores {}", result.best_move.unwrap(), result.score);
+/// }
+/// ```
+pub struct OrderChaosSearcher {
+    search: IterativeSearch<(WideBitboard, WideBitboard), OrderChaosMove>,
+}
+
+impl OrderChaosSearcher {
+    /// Returns a searcher with no depth limit and a budget of `ORDER_CHAOS_NODE_LIMIT` nodes per search.
+    pub fn new() -> OrderChaosSearcher {
+        OrderChaosSearcher {
+            search: IterativeSearch::new(ORDER_CHAOS_NODE_LIMIT),
+        }
+    }
+
+    /// Limits how many moves ahead the search looks.
+    pub fn with_max_depth(mut self, depth: u8) -> OrderChaosSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit.
+    pub fn with_node_limit(mut self, nodes: u64) -> OrderChaosSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+
+    /// Searches the position, see `OrderChaosSearcher`.
+    pub fn search(&mut self, pos: &mut OrderChaosBoard) -> OrderChaosSearchResult {
+        self.search.reset();
+        if !pos.is_in_play() {
+            return OrderChaosSearchResult {
+                best_move: None,
+                score: 0,
+                evaluation: None,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+        let remaining = pos.geometry().num_squares() - pos.num_moves();
+        let iteration = self.search.search(pos, remaining);
+        let order_score = for_mover(iteration.score, pos);
+        OrderChaosSearchResult {
+            best_move: iteration.best_move,
+            score: iteration.score,
+            evaluation: iteration
+                .solved
+                .then(|| to_evaluation(order_score, pos.num_moves())),
+            depth: iteration.depth,
+            nodes: self.search.nodes(),
+        }
+    }
+}
+
+impl Default for OrderChaosSearcher {
+    fn default() -> Self {
+        OrderChaosSearcher::new()
+    }
+}
+
+impl SearchPosition for OrderChaosBoard {
+    type Move = OrderChaosMove;
+    type Key = (WideBitboard, WideBitboard);
+
+    fn key(&self) -> Self::Key {
+        (self.x_bitboard, self.o_bitboard)
+    }
+
+    fn mover(&self) -> bool {
+        self.order_to_move()
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<OrderChaosMove> {
+        if self.last_move_won() {
+            // Order won, whoever made the line
+            Node::Solved(for_mover(WIN - self.num_moves() as i32, self))
+        } else if !self.order_can_win() {
+            // Chaos wins once the board fills up
+            let chaos_win = WIN - self.geometry().num_squares() as i32;
+            Node::Solved(for_mover(-chaos_win, self))
+        } else if depth == 0 {
+            Node::Horizon(for_mover(heuristic(self), self))
+        } else {
+            Node::Search
+        }
+    }
+
+    fn ordered_moves(&mut self) -> (Vec<OrderChaosMove>, bool) {
+        let moves = self
+            .geometry()
+            .centre_order()
+            .iter()
+            .copied()
+            .filter(|square| self.is_empty(*square))
+            .flat_map(|square| {
+                [Symbol::X, Symbol::O].map(|symbol| OrderChaosMove { square, symbol })
+            })
+            .collect();
+        (moves, true)
+    }
+
+    fn play(&mut self, mv: OrderChaosMove) {
+        self.play_unchecked(mv);
+    }
+
+    fn undo(&mut self) {
+        self.undo_unchecked();
+    }
+}
+
+// scores the lines Order can still complete from Order's point of view,
+// a line with n of one symbol and none of the other is worth 4^n
+fn heuristic(pos: &OrderChaosBoard) -> i32 {
+    let mut score: i64 = 0;
+    for line in pos.geometry().lines() {
+        let x = pos.x_bitboard.intersection(line).count_ones();
+        let o = pos.o_bitboard.intersection(line).count_ones();
+        if x == 0 || o == 0 {
+            score += 1 << (2 * x.max(o));
+        }
+    }
+    score.min(HEURISTIC_LIMIT as i64) as i32
+}
+
+// converts a score from Order's point of view into one for the player to move
+fn for_mover(order_score: i32, pos: &OrderChaosBoard) -> i32 {
+    if pos.order_to_move() {
+        order_score
+    } else {
+        -order_score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocked_board_is_solved_for_chaos() {
+        let pgn = "33x,26o,35x,15o,22x,18o,6x,29o,21x,34o,23x,19o,32x,13o,9x,3o,25x,28o,14x,16o,1x";
+        let mut pos = OrderChaosBoard::parse_pgn(pgn).unwrap();
+        assert!(pos.order_can_win());
+        // blocks the last open line, squares 7 to 11 in the second row
+        pos.play(OrderChaosMove {
+            square: 10,
+            symbol: Symbol::O,
+        })
+        .unwrap();
+        assert!(!pos.order_can_win());
+        assert!(pos.is_in_play());
+        // Chaos has won long before the board fills up, so even a one move search solves it
+        let result = OrderChaosSearcher::new().with_max_depth(1).search(&mut pos);
+        assert_eq!(result.evaluation, Some(Evaluation::OWinsIn(14)));
+        assert_eq!(result.depth, 1);
+    }
+}
@@ -0,0 +1,129 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::board::Board;
+use crate::evaluation::Square;
+use crate::game::{Engine, Game};
+use crate::rng::Rng;
+use crate::strength::Strength;
+
+/// Plays a `Board` at a given `Strength`, from random moves through looking a few moves ahead to the perfect solver,
+/// see `Board::engine_move`. The player keeps its own `Rng`, so games between seeded players can be replayed.
+/// ``` ignore
+/// use norts::{play_game, Board, Strength, StrengthPlayer};
+///
+/// fn main() {
+///     let mut perfect = StrengthPlayer::perfect(1);
+///     let mut medium = StrengthPlayer::depth_limited(2, 2);
+///     let end = play_game(&Board::new(), &mut medium, &mut perfect).unwrap();
+///     println!("{}: {}", end.pgn, end.situation());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StrengthPlayer {
+    strength: Strength,
+    rng: Rng,
+}
+
+impl StrengthPlayer {
+    pub fn new(strength: Strength, seed: u64) -> StrengthPlayer {
+        StrengthPlayer {
+            strength,
+            rng: Rng::new(seed),
+        }
+    }
+
+    /// Returns a player which never makes a mistake, choosing randomly between equally good moves.
+    pub fn perfect(seed: u64) -> StrengthPlayer {
+        StrengthPlayer::new(Strength::Perfect, seed)
+    }
+
+    /// Returns a player which plays any legal move.
+    pub fn random(seed: u64) -> StrengthPlayer {
+        StrengthPlayer::new(Strength::Random, seed)
+    }
+
+    /// Returns a player which only looks `depth` moves ahead, see `Strength::DepthLimited`.
+    pub fn depth_limited(depth: u8, seed: u64) -> StrengthPlayer {
+        StrengthPlayer::new(Strength::DepthLimited(depth), seed)
+    }
+
+    pub fn strength(&self) -> Strength {
+        self.strength
+    }
+}
+
+impl Engine<Board> for StrengthPlayer {
+    fn choose_move(&mut self, game: &Board) -> Option<Square> {
+        game.clone().engine_move(self.strength, &mut self.rng).ok()
+    }
+}
+
+/// A player whose moves come from a callback, such as a prompt on the command line or a request to a front end.
+/// It plays any `Game`, and can also be told about the opponent's moves with `HumanPlayer::on_opponent_move`.
+/// ``` ignore
+/// use norts::{play_game, Board, HumanPlayer, Searcher, Square};
+///
+/// fn main() {
+///     // always takes the lowest numbered empty square
+///     let mut human = HumanPlayer::new(|pos: &Board| {
+///         (0..9).find(|square| pos.is_valid_move(*square as i8)).and_then(Square::new).unwrap()
+///     });
+///     let end = play_game(&Board::new(), &mut human, &mut Searcher::new()).unwrap();
+///     end.show();
+/// }
+/// ```
+pub struct HumanPlayer<G: Game> {
+    choose: ChooseCallback<G>,
+    notify: Option<NotifyCallback<G>>,
+}
+
+type ChooseCallback<G> = Box<dyn FnMut(&G) -> <G as Game>::Move>;
+type NotifyCallback<G> = Box<dyn FnMut(&G, <G as Game>::Move)>;
+
+impl<G: Game> HumanPlayer<G> {
+    /// Returns a player which asks `choose` for each move. The callback is only called while the game is in play,
+    /// and a move which isn't legal stops `play_game`, so it should keep asking until it gets a legal one.
+    pub fn new(choose: impl FnMut(&G) -> G::Move + 'static) -> HumanPlayer<G> {
+        HumanPlayer {
+            choose: Box::new(choose),
+            notify: None,
+        }
+    }
+
+    /// Calls `notify` with the position and the move after each of the opponent's moves.
+    pub fn on_opponent_move(mut self, notify: impl FnMut(&G, G::Move) + 'static) -> HumanPlayer<G> {
+        self.notify = Some(Box::new(notify));
+        self
+    }
+}
+
+impl<G: Game> Engine<G> for HumanPlayer<G> {
+    fn choose_move(&mut self, game: &G) -> Option<G::Move> {
+        Some((self.choose)(game))
+    }
+
+    fn opponent_moved(&mut self, game: &G, mv: G::Move) {
+        if let Some(notify) = &mut self.notify {
+            notify(game, mv);
+        }
+    }
+}
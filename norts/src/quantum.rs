@@ -0,0 +1,754 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::bitboards::LINES;
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+use crate::iterative::{IterativeSearch, Node, SearchPosition};
+
+// squares of a spooky mark which has collapsed or was placed classically
+const COLLAPSED: [u8; 2] = [u8::MAX; 2];
+// a score of one point for X, counted in half points
+const POINT: i32 = 200;
+
+/// Nodes searched by a default `QuantumSearcher` before it settles for its last completed depth.
+pub const QUANTUM_NODE_LIMIT: u64 = 1_000_000;
+
+/// A move in Quantum tic-tac-toe. Squares are numbered like squares on a normal `Board`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QuantumMove {
+    /// Places a spooky mark in two squares at once, written as both squares, such as "04".
+    Spooky(u8, u8),
+    /// Collapses the spooky mark which closed a cycle into one of its squares, written as "c" and the square, such as "c4".
+    /// The rest of the cycle and everything entangled with it follows.
+    Collapse(u8),
+    /// Places a normal mark in the last square, written as the square.
+    Classical(u8),
+}
+
+impl fmt::Display for QuantumMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantumMove::Spooky(a, b) => write!(f, "{}{}", a, b),
+            QuantumMove::Collapse(square) => write!(f, "c{}", square),
+            QuantumMove::Classical(square) => write!(f, "{}", square),
+        }
+    }
+}
+
+// everything that matters for the rest of the game
+// marks are numbered by the move that placed them from 0, X placing the even numbered marks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct QuantumState {
+    // the two squares of each spooky mark, `COLLAPSED` once it is classical
+    marks: [[u8; 2]; 9],
+    num_marks: u8,
+    // one more than the mark sitting classically in each square, 0 if there is none
+    classical: [u8; 9],
+    // the last mark closed a cycle, which the player to move must collapse before moving
+    cycle: bool,
+    x_to_move: bool,
+}
+
+impl QuantumState {
+    fn new() -> QuantumState {
+        QuantumState {
+            marks: [[0; 2]; 9],
+            num_marks: 0,
+            classical: [0; 9],
+            cycle: false,
+            x_to_move: true,
+        }
+    }
+
+    #[inline(always)]
+    fn is_classical(&self, square: u8) -> bool {
+        self.classical[square as usize] != 0
+    }
+
+    // the spooky marks which have not collapsed yet
+    fn spooky_marks(&self) -> impl Iterator<Item = (usize, [u8; 2])> + '_ {
+        self.marks[..self.num_marks as usize]
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, squares)| *squares != COLLAPSED)
+    }
+
+    // returns true if the squares are joined by a chain of spooky marks in the entanglement graph
+    fn is_entangled(&self, from: u8, to: u8) -> bool {
+        let mut seen: u16 = 1 << from;
+        let mut stack = vec![from];
+        while let Some(square) = stack.pop() {
+            if square == to {
+                return true;
+            }
+            for (_, [a, b]) in self.spooky_marks() {
+                let next = if a == square {
+                    b
+                } else if b == square {
+                    a
+                } else {
+                    continue;
+                };
+                if seen & (1 << next) == 0 {
+                    seen |= 1 << next;
+                    stack.push(next);
+                }
+            }
+        }
+        false
+    }
+
+    // the classical marks of X and of O as bitboard masks
+    fn classical_masks(&self) -> (u16, u16) {
+        let (mut x, mut o) = (0, 0);
+        for square in 0..9 {
+            match self.classical[square] {
+                0 => (),
+                mark if mark % 2 == 1 => x |= 1 << square,
+                _ => o |= 1 << square,
+            }
+        }
+        (x, o)
+    }
+
+    // the highest numbered mark in each classical line of X and of O,
+    // as the line was only complete once that mark was placed
+    fn lines(&self) -> (Vec<u8>, Vec<u8>) {
+        let (x, o) = self.classical_masks();
+        let mut x_lines = Vec::new();
+        let mut o_lines = Vec::new();
+        for line in LINES {
+            let last_mark = || {
+                (0..9)
+                    .filter(|square| line & (1 << square) != 0)
+                    .map(|square| self.classical[square])
+                    .max()
+                    .unwrap()
+            };
+            if x & line == line {
+                x_lines.push(last_mark());
+            } else if o & line == line {
+                o_lines.push(last_mark());
+            }
+        }
+        (x_lines, o_lines)
+    }
+
+    // points won by X and O in half points, see `QuantumBoard::points`
+    fn half_points(&self) -> (i32, i32) {
+        let (x_lines, o_lines) = self.lines();
+        match (x_lines.iter().min(), o_lines.iter().min()) {
+            (None, None) => (0, 0),
+            (Some(_), None) => (2, 0),
+            (None, Some(_)) => (0, 2),
+            (Some(x), Some(o)) if x < o => (2, 1),
+            _ => (1, 2),
+        }
+    }
+
+    fn is_over(&self) -> bool {
+        let (x_lines, o_lines) = self.lines();
+        !x_lines.is_empty() || !o_lines.is_empty() || self.legal_moves().is_empty()
+    }
+
+    // moves in square order, ignoring whether the game is over
+    fn legal_moves(&self) -> Vec<QuantumMove> {
+        if self.cycle {
+            let [a, b] = self.marks[self.num_marks as usize - 1];
+            return vec![QuantumMove::Collapse(a), QuantumMove::Collapse(b)];
+        }
+        let open: Vec<u8> = (0..9)
+            .filter(|square| !self.is_classical(*square))
+            .collect();
+        if open.len() == 1 {
+            return vec![QuantumMove::Classical(open[0])];
+        }
+        let mut moves = Vec::new();
+        for (i, a) in open.iter().enumerate() {
+            for b in &open[i + 1..] {
+                moves.push(QuantumMove::Spooky(*a, *b));
+            }
+        }
+        moves
+    }
+
+    fn play(&self, mv: QuantumMove) -> QuantumState {
+        let mut state = *self;
+        match mv {
+            QuantumMove::Spooky(a, b) => {
+                state.cycle = self.is_entangled(a, b);
+                state.marks[state.num_marks as usize] = [a, b];
+                state.num_marks += 1;
+                state.x_to_move = !self.x_to_move;
+            }
+            QuantumMove::Collapse(square) => {
+                state.collapse(state.num_marks as usize - 1, square);
+                state.cycle = false;
+            }
+            QuantumMove::Classical(square) => {
+                state.marks[state.num_marks as usize] = COLLAPSED;
+                state.num_marks += 1;
+                state.classical[square as usize] = state.num_marks;
+                state.x_to_move = !self.x_to_move;
+            }
+        }
+        state
+    }
+
+    // makes the mark classical in the square, which forces every other spooky mark in that square into its other square,
+    // and so on through everything entangled with it
+    fn collapse(&mut self, mark: usize, square: u8) {
+        let mut stack = vec![(mark, square)];
+        while let Some((mark, square)) = stack.pop() {
+            if self.marks[mark] == COLLAPSED {
+                continue;
+            }
+            self.marks[mark] = COLLAPSED;
+            self.classical[square as usize] = mark as u8 + 1;
+            for (other, [a, b]) in self.spooky_marks() {
+                if a == square {
+                    stack.push((other, b));
+                } else if b == square {
+                    stack.push((other, a));
+                }
+            }
+        }
+    }
+}
+
+/// Quantum tic-tac-toe. Each move places a spooky mark in two squares at once, joining them in the entanglement graph.
+/// When a spooky mark closes a cycle in the graph, the other player chooses which of its two squares it collapses into
+/// before making their own move, see `QuantumMove::Collapse`, and every mark entangled with it becomes classical too.
+/// The last square, once every other square is classical, takes a normal mark.
+///
+/// The game ends once a collapse makes a line of classical marks. If both players have lines, the player whose line
+/// was completed by the lower numbered mark gets one point and the other half a point, otherwise the player with a line
+/// gets one point, see `QuantumBoard::points`.
+/// ``` ignore
+/// use norts::QuantumBoard;
+///
+/// fn main() {
+///     let mut pos = QuantumBoard::parse_pgn("04,48,08").unwrap();
+///     pos.show();
+///     println!("{:?}", pos.legal_moves()); // the cycle must be collapsed
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuantumBoard {
+    state: QuantumState,
+    history: Vec<QuantumMove>,
+}
+
+impl QuantumBoard {
+    pub fn new() -> QuantumBoard {
+        QuantumBoard {
+            state: QuantumState::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// a comma separated list of moves written as in `QuantumMove`, such as "04,48,08,c0".
+    pub fn parse_pgn(pgn: &str) -> Result<QuantumBoard, InvalidPgnError> {
+        let mut pos = QuantumBoard::new();
+        for part in pgn
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let digits: Vec<u8> = part
+                .trim_start_matches('c')
+                .chars()
+                .map(|c| {
+                    c.to_digit(10)
+                        .map(|digit| digit as u8)
+                        .ok_or(InvalidPgnError)
+                })
+                .collect::<Result<_, _>>()?;
+            let mv = match (part.starts_with('c'), &digits[..]) {
+                (true, &[square]) => QuantumMove::Collapse(square),
+                (false, &[square]) => QuantumMove::Classical(square),
+                (false, &[a, b]) => QuantumMove::Spooky(a, b),
+                _ => return Err(InvalidPgnError),
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `QuantumBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history
+            .iter()
+            .map(|mv| mv.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn history(&self) -> &[QuantumMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    /// After a cycle the player to move collapses it and then moves again.
+    pub fn current_player(&self) -> i8 {
+        if self.state.x_to_move {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Returns true if the last spooky mark closed a cycle, so the next move must be a `QuantumMove::Collapse`.
+    pub fn needs_collapse(&self) -> bool {
+        self.state.cycle
+    }
+
+    /// Returns 'X' or 'O' and the number of the mark sitting classically in the square, if there is one.
+    /// Marks are numbered from 1 in the order they were placed. Squares past 8 have no mark.
+    pub fn classical_mark(&self, square: u8) -> Option<(char, u8)> {
+        match *self.state.classical.get(square as usize)? {
+            0 => None,
+            mark if mark % 2 == 1 => Some(('X', mark)),
+            mark => Some(('O', mark)),
+        }
+    }
+
+    /// Returns the numbers of the spooky marks in the square which have not collapsed yet.
+    pub fn spooky_marks(&self, square: u8) -> Vec<u8> {
+        self.state
+            .spooky_marks()
+            .filter(|(_, squares)| squares.contains(&square))
+            .map(|(mark, _)| mark as u8 + 1)
+            .collect()
+    }
+
+    /// Spooky marks may give their squares either way round.
+    pub fn is_valid_move(&self, mv: QuantumMove) -> bool {
+        self.legal_moves().contains(&normalise(mv))
+    }
+
+    /// Returns every legal move: the two collapses after a cycle,
+    /// otherwise every pair of squares without a classical mark, or the last square once only one is left.
+    pub fn legal_moves(&self) -> Vec<QuantumMove> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        self.state.legal_moves()
+    }
+
+    pub fn play(&mut self, mv: QuantumMove) -> Result<bool, IllegalMoveError> {
+        if !self.is_valid_move(mv) {
+            return Err(IllegalMoveError);
+        }
+        let mv = normalise(mv);
+        self.state = self.state.play(mv);
+        self.history.push(mv);
+        Ok(true)
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        self.history.pop().ok_or(NoMoveToUndoError)?;
+        // collapses can't be reversed, so the game is replayed
+        self.state = self
+            .history
+            .iter()
+            .fold(QuantumState::new(), |state, mv| state.play(*mv));
+        Ok(())
+    }
+
+    /// Returns the points won by X and O, which are 0 until the game ends.
+    /// A player with a line gets one point, unless both players have lines,
+    /// in which case the line completed by the lower numbered mark gets one point and the other half a point.
+    pub fn points(&self) -> (f32, f32) {
+        let (x, o) = self.state.half_points();
+        (x as f32 / 2.0, o as f32 / 2.0)
+    }
+
+    /// Returns `Outcome::XWon` or `Outcome::OWon` for whoever scored more points once the game is over,
+    /// `Outcome::Draw` if nobody did, or `Outcome::Ongoing`.
+    pub fn situation(&self) -> Outcome {
+        if !self.state.is_over() {
+            return Outcome::Ongoing;
+        }
+        let (x, o) = self.state.half_points();
+        match x.cmp(&o) {
+            std::cmp::Ordering::Greater => Outcome::XWon,
+            std::cmp::Ordering::Less => Outcome::OWon,
+            std::cmp::Ordering::Equal => Outcome::Draw,
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        !self.state.is_over()
+    }
+
+    /// Returns the best move found by a default `QuantumSearcher`.
+    pub fn best_move(&self) -> Result<QuantumMove, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut QuantumSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &self,
+        searcher: &mut QuantumSearcher,
+    ) -> Result<QuantumMove, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            Ok(searcher.search(self).best_move.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("Board:");
+        print!("{}", self);
+        println!("Squares:");
+        for row in 0..3 {
+            println!(" {}  {}  {}", row * 3, row * 3 + 1, row * 3 + 2);
+        }
+        if self.needs_collapse() {
+            println!("The last mark closed a cycle, which must be collapsed");
+        }
+    }
+}
+
+impl Default for QuantumBoard {
+    fn default() -> Self {
+        QuantumBoard::new()
+    }
+}
+
+/// Classical marks are shown in capitals and spooky marks in lower case, each with its number.
+impl fmt::Display for QuantumBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let cells: Vec<String> = (0..9)
+            .map(|square| match self.classical_mark(square) {
+                Some((player, mark)) => format!("{}{}", player, mark),
+                None => self
+                    .spooky_marks(square)
+                    .iter()
+                    .map(|mark| format!("{}{}", if mark % 2 == 1 { 'x' } else { 'o' }, mark))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            })
+            .collect();
+        let width = cells.iter().map(String::len).max().unwrap().max(1);
+        for row in 0..3 {
+            let line: Vec<String> = (0..3)
+                .map(|col| {
+                    let cell = &cells[row * 3 + col];
+                    format!(
+                        "{:^width$}",
+                        if cell.is_empty() { "." } else { cell },
+                        width = width
+                    )
+                })
+                .collect();
+            writeln!(f, " {} ", line.join(" | "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The result of searching a `QuantumBoard`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantumSearchResult {
+    pub best_move: Option<QuantumMove>,
+    /// Score from the point of view of the player to move. Finished games score 200 for each point ahead,
+    /// less the number of marks placed so quicker wins are preferred; anything smaller is a heuristic estimate.
+    pub score: i32,
+    /// X's points less O's with best play from both sides, if the search was able to solve the position.
+    pub points_ahead: Option<f32>,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search for `QuantumBoard`s with a transposition table.
+/// Each iteration searches one move deeper until the position is solved,
+/// the maximum depth is reached or the node budget runs out, in which case the last completed iteration is used.
+/// Positions past the depth limit are scored by the lines each player has started with classical marks.
+/// ``` ignore
+/// use norts::{QuantumBoard, QuantumSearcher};
+///
+/// fn main() {
+///     let mut pos = QuantumBoard::parse_pgn("04,48,08").unwrap();
+///     let result = QuantumSearcher::new().with_max_depth(4).search(&mut pos);
+///     println!("{} scores {}", result.best_move.unwrap(), result.score);
+/// }
+/// ```
+pub struct QuantumSearcher {
+    search: IterativeSearch<QuantumState, QuantumMove>,
+}
+
+impl QuantumSearcher {
+    /// Returns a searcher with no depth limit and a budget of `QUANTUM_NODE_LIMIT` nodes per search.
+    pub fn new() -> QuantumSearcher {
+        QuantumSearcher {
+            search: IterativeSearch::new(QUANTUM_NODE_LIMIT),
+        }
+    }
+
+    /// Limits how many moves ahead the search looks, counting collapses as moves.
+    pub fn with_max_depth(mut self, depth: u8) -> QuantumSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit.
+    pub fn with_node_limit(mut self, nodes: u64) -> QuantumSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+
+    /// Searches the position, see `QuantumSearcher`.
+    pub fn search(&mut self, pos: &QuantumBoard) -> QuantumSearchResult {
+        self.search.reset();
+        let state = pos.state;
+        if state.is_over() {
+            return QuantumSearchResult {
+                best_move: None,
+                score: 0,
+                points_ahead: None,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+        // at most 9 marks and 9 collapses are left
+        let iteration = self.search.search(
+            &mut QuantumLine {
+                states: vec![state],
+            },
+            18,
+        );
+        let x_score = for_mover(iteration.score, &state);
+        QuantumSearchResult {
+            best_move: iteration.best_move,
+            score: iteration.score,
+            points_ahead: iteration
+                .solved
+                .then(|| (x_score as f32 / (POINT / 2) as f32).round() / 2.0),
+            depth: iteration.depth,
+            nodes: self.search.nodes(),
+        }
+    }
+}
+
+impl Default for QuantumSearcher {
+    fn default() -> Self {
+        QuantumSearcher::new()
+    }
+}
+
+// the states from the root of a search to the position being searched
+struct QuantumLine {
+    states: Vec<QuantumState>,
+}
+
+impl QuantumLine {
+    fn state(&self) -> &QuantumState {
+        self.states.last().unwrap()
+    }
+}
+
+impl SearchPosition for QuantumLine {
+    type Move = QuantumMove;
+    type Key = QuantumState;
+
+    fn key(&self) -> QuantumState {
+        *self.state()
+    }
+
+    // a collapse is followed by a move from the same player
+    fn mover(&self) -> bool {
+        self.state().x_to_move
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<QuantumMove> {
+        let state = self.state();
+        if state.is_over() {
+            Node::Solved(for_mover(final_score(state), state))
+        } else if depth == 0 {
+            Node::Horizon(for_mover(heuristic(state), state))
+        } else {
+            Node::Search
+        }
+    }
+
+    fn ordered_moves(&mut self) -> (Vec<QuantumMove>, bool) {
+        (ordered_moves(self.state()), true)
+    }
+
+    fn play(&mut self, mv: QuantumMove) {
+        let child = self.state().play(mv);
+        self.states.push(child);
+    }
+
+    fn undo(&mut self) {
+        self.states.pop();
+    }
+}
+
+// puts the squares of a spooky mark in ascending order, as they are in `QuantumBoard::legal_moves`
+fn normalise(mv: QuantumMove) -> QuantumMove {
+    match mv {
+        QuantumMove::Spooky(a, b) if a > b => QuantumMove::Spooky(b, a),
+        mv => mv,
+    }
+}
+
+// converts a score from X's point of view into one for the player to move
+#[inline(always)]
+fn for_mover(x_score: i32, state: &QuantumState) -> i32 {
+    if state.x_to_move {
+        x_score
+    } else {
+        -x_score
+    }
+}
+
+// the half points X is ahead by, less the marks placed so quicker wins score higher
+fn final_score(state: &QuantumState) -> i32 {
+    let (x, o) = state.half_points();
+    let score = (x - o) * POINT / 2;
+    score - score.signum() * state.num_marks as i32
+}
+
+// counts the classical marks in lines the other player hasn't blocked classically
+fn heuristic(state: &QuantumState) -> i32 {
+    let (x, o) = state.classical_masks();
+    let mut score = 0;
+    for line in LINES {
+        let x_marks = (x & line).count_ones() as i32;
+        let o_marks = (o & line).count_ones() as i32;
+        if o_marks == 0 {
+            score += x_marks;
+        } else if x_marks == 0 {
+            score -= o_marks;
+        }
+    }
+    score
+}
+
+// spooky marks touching the centre and corners first, as those squares are in the most lines
+fn ordered_moves(state: &QuantumState) -> Vec<QuantumMove> {
+    let lines_through = |square: u8| {
+        LINES
+            .iter()
+            .filter(|line| *line & (1 << square) != 0)
+            .count()
+    };
+    let mut moves = state.legal_moves();
+    moves.sort_by_key(|mv| match mv {
+        QuantumMove::Spooky(a, b) => std::cmp::Reverse(lines_through(*a) + lines_through(*b)),
+        QuantumMove::Collapse(square) | QuantumMove::Classical(square) => {
+            std::cmp::Reverse(lines_through(*square))
+        }
+    });
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // X's half points less O's with best play, found by trying every line
+    fn brute_force(state: &QuantumState) -> i32 {
+        if state.is_over() {
+            let (x, o) = state.half_points();
+            return x - o;
+        }
+        let scores = state
+            .legal_moves()
+            .into_iter()
+            .map(|mv| brute_force(&state.play(mv)));
+        if state.x_to_move {
+            scores.max().unwrap()
+        } else {
+            scores.min().unwrap()
+        }
+    }
+
+    #[test]
+    fn search_matches_brute_force() {
+        let mut rng = Rng::new(7);
+        let mut checked = 0;
+        while checked < 300 {
+            let mut pos = QuantumBoard::new();
+            for _ in 0..6 + rng.below(3) {
+                if !pos.is_in_play() {
+                    break;
+                }
+                let moves = pos.legal_moves();
+                pos.play(*rng.choose(&moves).unwrap()).unwrap();
+            }
+            // mostly positions needing a collapse, where the turn doesn't pass
+            if !pos.is_in_play() || (checked % 4 != 0 && !pos.needs_collapse()) {
+                continue;
+            }
+            let result = QuantumSearcher::new().search(&pos);
+            let expected = brute_force(&pos.state);
+            assert_eq!(
+                result.points_ahead,
+                Some(expected as f32 / 2.0),
+                "{}",
+                pos.pgn()
+            );
+            // the chosen move must keep the value of the position
+            let child = pos.state.play(result.best_move.unwrap());
+            assert_eq!(brute_force(&child), expected, "{}", pos.pgn());
+            checked += 1;
+        }
+    }
+
+    #[test]
+    fn collapse_at_root() {
+        let pos = QuantumBoard::parse_pgn("04,36,36,c6,78,25,04").unwrap();
+        assert!(pos.needs_collapse());
+        let result = QuantumSearcher::new().search(&pos);
+        assert_eq!(result.points_ahead, Some(1.0));
+        // O collapses, into a loss by a point or a draw
+        let pos = QuantumBoard::parse_pgn("47,14,67,12,12").unwrap();
+        assert_eq!(QuantumSearcher::new().search(&pos).points_ahead, Some(-1.0));
+    }
+}
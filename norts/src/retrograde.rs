@@ -0,0 +1,140 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::evaluation::Evaluation;
+
+/// The value of a position for the player to move, with the number of moves until the game ends.
+/// Positions which neither side can force to an end are draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Value {
+    Win(u16),
+    Loss(u16),
+    Draw,
+}
+
+impl Value {
+    /// The value for the player who moved into a position with this value.
+    pub(crate) fn for_mover(self) -> Value {
+        match self {
+            Value::Win(n) => Value::Loss(n + 1),
+            Value::Loss(n) => Value::Win(n + 1),
+            Value::Draw => Value::Draw,
+        }
+    }
+
+    /// Converts a value for the player to move into an evaluation.
+    pub(crate) fn to_evaluation(self, x_to_move: bool) -> Evaluation {
+        let distance = |n: u16| n.min(u8::MAX as u16) as u8;
+        match (self, x_to_move) {
+            (Value::Win(n), true) | (Value::Loss(n), false) => Evaluation::XWinsIn(distance(n)),
+            (Value::Win(n), false) | (Value::Loss(n), true) => Evaluation::OWinsIn(distance(n)),
+            (Value::Draw, _) => Evaluation::Draw,
+        }
+    }
+
+    /// Orders values from the point of view of the player they belong to,
+    /// quicker wins and slower losses being better.
+    pub(crate) fn rank(self) -> i32 {
+        match self {
+            Value::Win(n) => i32::from(u16::MAX) - i32::from(n),
+            Value::Draw => 0,
+            Value::Loss(n) => -i32::from(u16::MAX) + i32::from(n),
+        }
+    }
+}
+
+/// Solves every position reachable from `start` by retrograde analysis,
+/// for games which can go round in circles so that plain minimax would never finish.
+///
+/// `terminal` gives the value of positions where the game is over.
+/// Solving then works backwards from them: a position is won as soon as one move leads to a lost position,
+/// and lost once every move leads to a won one. Positions are handled in order of distance from the end,
+/// so the first time a position is solved its distance is the shortest win or longest loss.
+/// Whatever is left at the end can be kept going forever by one side, and is a draw.
+pub(crate) fn solve<S, C, T>(start: S, children: C, terminal: T) -> HashMap<S, Value>
+where
+    S: Copy + Eq + Hash,
+    C: Fn(&S) -> Vec<S>,
+    T: Fn(&S) -> Option<Value>,
+{
+    // number every reachable position and link each to the positions one move before it
+    let mut index = HashMap::new();
+    let mut states = vec![start];
+    let mut parents: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut remaining = Vec::new();
+    let mut values = Vec::new();
+    let mut queue = VecDeque::new();
+    index.insert(start, 0);
+    let mut next = 0;
+    while next < states.len() {
+        let state = states[next];
+        let value = terminal(&state);
+        if value.is_some() {
+            queue.push_back(next);
+            remaining.push(0);
+        } else {
+            let moves = children(&state);
+            remaining.push(moves.len());
+            for child in moves {
+                let child_index = *index.entry(child).or_insert_with(|| {
+                    states.push(child);
+                    parents.push(Vec::new());
+                    states.len() - 1
+                });
+                parents[child_index].push(next);
+            }
+        }
+        values.push(value);
+        next += 1;
+    }
+
+    while let Some(solved) = queue.pop_front() {
+        let value = values[solved].unwrap();
+        for parent in parents[solved].clone() {
+            if values[parent].is_some() {
+                continue;
+            }
+            match value {
+                Value::Loss(n) => {
+                    values[parent] = Some(Value::Win(n + 1));
+                    queue.push_back(parent);
+                }
+                Value::Win(n) => {
+                    remaining[parent] -= 1;
+                    if remaining[parent] == 0 {
+                        values[parent] = Some(Value::Loss(n + 1));
+                        queue.push_back(parent);
+                    }
+                }
+                Value::Draw => (),
+            }
+        }
+    }
+
+    states
+        .into_iter()
+        .zip(values)
+        .map(|(state, value)| (state, value.unwrap_or(Value::Draw)))
+        .collect()
+}
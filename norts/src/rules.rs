@@ -0,0 +1,99 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::bitboards::Bitboard;
+use crate::evaluation::Outcome;
+
+/// Which win condition a game is played with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Rules {
+    /// Completing a line wins.
+    #[default]
+    Standard,
+    /// Misère, or reverse, tic-tac-toe where completing a line loses.
+    Misere,
+    /// Infinite tic-tac-toe, where each player keeps at most three marks and placing a fourth
+    /// removes their oldest one, so the board never fills up. Completing a line wins and
+    /// the game is drawn when a position comes up for the third time.
+    ///
+    /// Which mark vanishes next depends on the order of the moves, which a `Bitboard` doesn't know,
+    /// so these games have to be played through a `Board`. It solves them by retrograde analysis
+    /// rather than searching, which would never finish as positions can repeat.
+    Infinite,
+}
+
+impl Rules {
+    /// Returns the outcome of the position under these rules.
+    /// Draws by repetition under `Rules::Infinite` need the move history, see `Board::situation`.
+    #[inline(always)]
+    pub fn outcome(self, pos: &Bitboard) -> Outcome {
+        let (x_line, o_line) = (pos.x_won(), pos.o_won());
+        match (self, x_line, o_line) {
+            (Rules::Standard | Rules::Infinite, true, _) | (Rules::Misere, _, true) => {
+                Outcome::XWon
+            }
+            (Rules::Standard | Rules::Infinite, _, true) | (Rules::Misere, true, _) => {
+                Outcome::OWon
+            }
+            _ if pos.is_draw() => Outcome::Draw,
+            _ => Outcome::Ongoing,
+        }
+    }
+
+    /// Returns the search score of a finished game, `100 - num_moves` if X won,
+    /// `-100 + num_moves` if O won and 0 for a draw, or `None` if the game is ongoing.
+    #[inline(always)]
+    pub(crate) fn terminal_score(self, pos: &Bitboard) -> Option<i8> {
+        match self.outcome(pos) {
+            Outcome::XWon => Some(100 - pos.num_moves() as i8),
+            Outcome::OWon => Some(-100 + pos.num_moves() as i8),
+            Outcome::Draw => Some(0),
+            Outcome::Ongoing => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboards::legal_positions;
+    use crate::board::Board;
+    use crate::evaluation::{Evaluation, Square};
+
+    #[test]
+    fn misere_flips_the_winner() {
+        for pos in legal_positions() {
+            let flipped = match Rules::Standard.outcome(&pos) {
+                Outcome::XWon => Outcome::OWon,
+                Outcome::OWon => Outcome::XWon,
+                outcome => outcome,
+            };
+            assert_eq!(Rules::Misere.outcome(&pos), flipped);
+        }
+    }
+
+    #[test]
+    fn misere_centre_is_the_only_safe_opening() {
+        let (eval, moves) = Board::with_rules(Rules::Misere).best_moves().unwrap();
+        assert_eq!(eval, Evaluation::Draw);
+        assert_eq!(moves, vec![Square::new(4).unwrap()]);
+    }
+}
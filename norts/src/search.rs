@@ -19,67 +19,379 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
+use std::fmt;
+use std::fmt::Formatter;
+
 use crate::bitboards::Bitboard;
+use crate::evaluation::{Evaluation, Square};
+use crate::rules::Rules;
+use crate::transposition::{Bound, Entry, TranspositionTable};
 
 /// Uses a strong solved minimax algorithm with alpha-beta pruning
 /// to search the game tree.
 /// Moves are played and then undone to avoid the memory intense process
 /// of copying the board.
-pub fn search(pos: &mut Bitboard, mut alpha: i8, mut beta: i8) -> (i8, u8) {
-    if pos.x_won() {
-        return (100 - (pos.num_moves() as i8), 9);
+pub fn search(pos: &mut Bitboard, alpha: i8, beta: i8, rules: Rules) -> (i8, u8) {
+    Searcher { table: None, rules }.alpha_beta(pos, alpha, beta)
+}
+
+/// Searches every legal move with a full window so that each eval is exact,
+/// returning `(square, eval)` pairs in square order.
+pub fn search_moves(pos: &mut Bitboard, rules: Rules) -> Vec<(u8, i8)> {
+    Searcher { table: None, rules }.score_moves(pos)
+}
+
+/// Scores every legal move by plain minimax looking only `depth` moves ahead, including the move itself.
+/// Positions past the horizon are scored as 0, so this is only used to play deliberately weaker moves.
+pub fn search_moves_to_depth(pos: &mut Bitboard, depth: u8, rules: Rules) -> Vec<(u8, i8)> {
+    let mut evals = Vec::new();
+    for square in 0..9 {
+        if !pos.is_legal(square) {
+            continue;
+        }
+        pos.play(square);
+        evals.push((
+            square,
+            minimax_to_depth(pos, depth.saturating_sub(1), rules),
+        ));
+        pos.clear_square(square);
     }
-    if pos.o_won() {
-        return (-100 + (pos.num_moves() as i8), 9);
+    evals
+}
+
+fn minimax_to_depth(pos: &mut Bitboard, depth: u8, rules: Rules) -> i8 {
+    if let Some(score) = rules.terminal_score(pos) {
+        return score;
     }
-    if pos.is_draw() {
-        return (0, 9);
+    if depth == 0 {
+        return 0;
     }
-    // if X is playing, the engine wants to maximise the eval
-    return if pos.current_player() {
-        let mut max_eval = i8::MIN + 10;
-        let mut max_move: u8 = 9;
-        for square in 0..9 {
-            if !pos.is_legal(square) {
-                continue;
+    let x_to_move = pos.current_player();
+    let mut best = if x_to_move { i8::MIN } else { i8::MAX };
+    for square in 0..9 {
+        if !pos.is_legal(square) {
+            continue;
+        }
+        pos.play(square);
+        let eval = minimax_to_depth(pos, depth - 1, rules);
+        pos.clear_square(square);
+        if (x_to_move && eval > best) || (!x_to_move && eval < best) {
+            best = eval;
+        }
+    }
+    best
+}
+
+/// Returned when a `Searcher` is asked to play by `Rules::Infinite`.
+/// Positions repeat under those rules, so they are solved by `Board` instead, see `Board::best_move`.
+#[derive(Debug, Clone)]
+pub struct UnsupportedRulesError;
+
+impl fmt::Display for UnsupportedRulesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Infinite games can't be searched, play them through a Board."
+        )
+    }
+}
+
+/// Reusable searcher which can own a transposition table,
+/// so that work done in one call to `search` is kept for the next.
+/// ``` ignore
+/// use norts::{Board, Searcher};
+///
+/// fn main() {
+///     let mut searcher = Searcher::with_table();
+///     let mut pos = Board::parse_pgn("04").unwrap();
+///     println!("Best move: {}", pos.best_move_with(&mut searcher).unwrap());
+///     println!("{:?}", searcher.table().unwrap().stats());
+/// }
+/// ```
+pub struct Searcher {
+    table: Option<TranspositionTable>,
+    rules: Rules,
+}
+
+impl Searcher {
+    /// Returns a searcher without a transposition table.
+    pub fn new() -> Searcher {
+        Searcher {
+            table: None,
+            rules: Rules::Standard,
+        }
+    }
+
+    /// Returns a searcher without a transposition table which plays by the given rules,
+    /// or an error for `Rules::Infinite`.
+    pub fn with_rules(rules: Rules) -> Result<Searcher, UnsupportedRulesError> {
+        let mut searcher = Searcher::new();
+        searcher.set_rules(rules)?;
+        Ok(searcher)
+    }
+
+    /// Returns a searcher with an empty transposition table.
+    pub fn with_table() -> Searcher {
+        Searcher::with_transposition_table(TranspositionTable::new())
+    }
+
+    /// Returns a searcher with an empty transposition table which stores
+    /// symmetric positions once, see `TranspositionTable::with_symmetry`.
+    pub fn with_symmetric_table() -> Searcher {
+        Searcher::with_transposition_table(TranspositionTable::with_symmetry())
+    }
+
+    /// Returns a searcher using an existing table, such as one loaded from disk.
+    pub fn with_transposition_table(table: TranspositionTable) -> Searcher {
+        Searcher {
+            table: Some(table),
+            rules: Rules::Standard,
+        }
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Changes the rules the searcher plays by, or returns an error for `Rules::Infinite`.
+    /// The table is cleared if the rules change, as results from one set of rules are wrong for another.
+    pub fn set_rules(&mut self, rules: Rules) -> Result<(), UnsupportedRulesError> {
+        if rules == Rules::Infinite {
+            return Err(UnsupportedRulesError);
+        }
+        self.switch_rules(rules);
+        Ok(())
+    }
+
+    // changes the rules for a `Board` which has already dealt with `Rules::Infinite` itself
+    pub(crate) fn switch_rules(&mut self, rules: Rules) {
+        if rules != self.rules {
+            if let Some(table) = &mut self.table {
+                table.clear();
             }
-            pos.play(square);
-            let eval = search(pos, alpha, beta).0;
-            pos.clear_square(square);
-            if eval > max_eval {
-                max_eval = eval;
-                max_move = square;
-                if eval > beta {
-                    break;
-                }
-                if eval > alpha {
-                    alpha = eval;
+            self.rules = rules;
+        }
+    }
+
+    pub fn table(&self) -> Option<&TranspositionTable> {
+        self.table.as_ref()
+    }
+
+    pub fn table_mut(&mut self) -> Option<&mut TranspositionTable> {
+        self.table.as_mut()
+    }
+
+    /// Removes the table from the searcher and returns it.
+    pub fn take_table(&mut self) -> Option<TranspositionTable> {
+        self.table.take()
+    }
+
+    /// Solves the position, returning its evaluation and the best move,
+    /// or `None` if the game has already ended.
+    pub fn search(&mut self, pos: &mut Bitboard) -> (Evaluation, Option<Square>) {
+        let (score, best_move) = self.alpha_beta(pos, i8::MIN, i8::MAX);
+        (
+            Evaluation::from_score(score, pos.num_moves()),
+            Square::new(best_move),
+        )
+    }
+
+    /// Returns the principal variation, the sequence of best moves for both sides
+    /// until the game ends. Empty if the game has already ended.
+    pub fn principal_variation(&mut self, pos: &mut Bitboard) -> Vec<Square> {
+        let mut line = Vec::new();
+        loop {
+            let best_move = self.alpha_beta(pos, i8::MIN, i8::MAX).1;
+            match Square::new(best_move) {
+                Some(square) => {
+                    pos.play(best_move);
+                    line.push(square);
                 }
+                None => break,
             }
         }
-        (max_eval, max_move)
-    } else {
-        // if O is playing, the engine wants to minimise the eval
-        let mut min_eval = i8::MAX - 10;
-        let mut min_move: u8 = 9;
+        // undo the line to leave the position as it was
+        for square in &line {
+            pos.clear_square(square.index());
+        }
+        line
+    }
+
+    /// Solves every legal move in the position, returning each square in order
+    /// along with the evaluation of the position if that move is played.
+    /// Unlike `Searcher::search` no move is cut off early, so ties between moves are kept.
+    pub fn search_moves(&mut self, pos: &mut Bitboard) -> Vec<(Square, Evaluation)> {
+        let num_moves = pos.num_moves();
+        self.score_moves(pos)
+            .into_iter()
+            .map(|(square, score)| {
+                (
+                    Square::new(square).unwrap(),
+                    Evaluation::from_score(score, num_moves),
+                )
+            })
+            .collect()
+    }
+
+    /// Searches every legal move with a full window so that each eval is exact,
+    /// returning `(square, eval)` pairs in square order.
+    pub(crate) fn score_moves(&mut self, pos: &mut Bitboard) -> Vec<(u8, i8)> {
+        let mut evals = Vec::new();
         for square in 0..9 {
             if !pos.is_legal(square) {
                 continue;
             }
             pos.play(square);
-            let eval = search(pos, alpha, beta).0;
+            evals.push((square, self.alpha_beta(pos, i8::MIN, i8::MAX).0));
             pos.clear_square(square);
-            if eval < min_eval {
-                min_eval = eval;
-                min_move = square;
-                if eval < alpha {
-                    break;
+        }
+        evals
+    }
+
+    /// Searches the position, returning the eval and the best move, or 9 if there is none.
+    /// The eval is `100 - num_moves` for an X win and `-100 + num_moves` for an O win,
+    /// so quicker wins are preferred.
+    pub(crate) fn alpha_beta(
+        &mut self,
+        pos: &mut Bitboard,
+        mut alpha: i8,
+        mut beta: i8,
+    ) -> (i8, u8) {
+        if let Some(score) = self.rules.terminal_score(pos) {
+            return (score, 9);
+        }
+        if let Some(table) = &mut self.table {
+            if let Some(entry) = table.probe(pos) {
+                match entry.bound {
+                    Bound::Exact => return (entry.eval, entry.best_move),
+                    Bound::Lower if entry.eval >= beta => return (entry.eval, entry.best_move),
+                    Bound::Upper if entry.eval <= alpha => return (entry.eval, entry.best_move),
+                    _ => (),
+                }
+            }
+        }
+        let (alpha_orig, beta_orig) = (alpha, beta);
+        // if X is playing, the engine wants to maximise the eval
+        let (eval, best_move) = if pos.current_player() {
+            let mut max_eval = i8::MIN + 10;
+            let mut max_move: u8 = 9;
+            for square in 0..9 {
+                if !pos.is_legal(square) {
+                    continue;
+                }
+                pos.play(square);
+                let eval = self.alpha_beta(pos, alpha, beta).0;
+                pos.clear_square(square);
+                if eval > max_eval {
+                    max_eval = eval;
+                    max_move = square;
+                    if eval > beta {
+                        break;
+                    }
+                    if eval > alpha {
+                        alpha = eval;
+                    }
                 }
-                if eval < beta {
-                    beta = eval;
+            }
+            (max_eval, max_move)
+        } else {
+            // if O is playing, the engine wants to minimise the eval
+            let mut min_eval = i8::MAX - 10;
+            let mut min_move: u8 = 9;
+            for square in 0..9 {
+                if !pos.is_legal(square) {
+                    continue;
+                }
+                pos.play(square);
+                let eval = self.alpha_beta(pos, alpha, beta).0;
+                pos.clear_square(square);
+                if eval < min_eval {
+                    min_eval = eval;
+                    min_move = square;
+                    if eval < alpha {
+                        break;
+                    }
+                    if eval < beta {
+                        beta = eval;
+                    }
                 }
             }
+            (min_eval, min_move)
+        };
+        if let Some(table) = &mut self.table {
+            // results outside the window are only bounds on the true value
+            let bound = if eval <= alpha_orig {
+                Bound::Upper
+            } else if eval >= beta_orig {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            table.store(
+                *pos,
+                Entry {
+                    eval,
+                    best_move,
+                    bound,
+                },
+            );
         }
-        (min_eval, min_move)
-    };
+        (eval, best_move)
+    }
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Searcher::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboards::legal_positions;
+
+    // the symmetric table may give back a mirror image of the move, so moves are compared by their score
+    fn matches_plain_search(mut searcher: Searcher, same_moves: bool) {
+        let positions = legal_positions();
+        assert_eq!(positions.len(), 5478);
+        for mut pos in positions {
+            let expected = search(&mut pos, i8::MIN, i8::MAX, Rules::Standard);
+            let (score, best_move) = searcher.alpha_beta(&mut pos, i8::MIN, i8::MAX);
+            assert_eq!(score, expected.0);
+            if same_moves || best_move == 9 {
+                assert_eq!(best_move, expected.1);
+            } else {
+                assert!(pos.is_legal(best_move));
+                pos.play(best_move);
+                assert_eq!(search(&mut pos, i8::MIN, i8::MAX, Rules::Standard).0, score);
+            }
+        }
+    }
+
+    #[test]
+    fn table_matches_plain_search() {
+        matches_plain_search(Searcher::with_table(), true);
+    }
+
+    #[test]
+    fn symmetric_table_matches_plain_search() {
+        matches_plain_search(Searcher::with_symmetric_table(), false);
+    }
+
+    #[test]
+    fn table_stats() {
+        let mut searcher = Searcher::with_table();
+        searcher.search(&mut Bitboard::new());
+        let stats = searcher.table().unwrap().stats();
+        assert!(stats.misses > 0);
+        // positions searched with different windows are stored more than once
+        assert!(stats.stores as usize >= searcher.table().unwrap().len());
+        // the root is now stored exactly, so searching it again is a single hit
+        searcher.search(&mut Bitboard::new());
+        let again = searcher.table().unwrap().stats();
+        assert_eq!(again.hits, stats.hits + 1);
+        assert_eq!(again.misses, stats.misses);
+        assert_eq!(again.stores, stats.stores);
+    }
 }
@@ -0,0 +1,145 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::bitboards::Bitboard;
+use crate::rng::Rng;
+use crate::rules::Rules;
+use crate::search::{search_moves, search_moves_to_depth};
+use crate::tablebase::Tablebase;
+
+/// How well the engine plays, see `Board::engine_move`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strength {
+    /// Plays any legal move.
+    Random,
+    /// Only looks the given number of moves ahead, choosing randomly between moves that look equal.
+    /// 1 spots immediate wins, 2 also blocks immediate losses.
+    DepthLimited(u8),
+    /// Plays perfectly, except that with the given probability it deliberately plays
+    /// a move which makes its result worse, when one exists.
+    Blunder(f64),
+    /// Plays perfectly, except that with the given probability it plays a random legal move.
+    EpsilonOptimal(f64),
+    /// Never makes a mistake, choosing randomly between equally good moves.
+    Perfect,
+}
+
+impl fmt::Display for Strength {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Strength::Random => write!(f, "Random"),
+            Strength::DepthLimited(depth) => write!(f, "Depth {}", depth),
+            Strength::Blunder(probability) => write!(f, "Blunders {}%", probability * 100.0),
+            Strength::EpsilonOptimal(epsilon) => write!(f, "Random {}%", epsilon * 100.0),
+            Strength::Perfect => write!(f, "Perfect"),
+        }
+    }
+}
+
+/// Picks a move at the given strength, or returns `None` if the game has ended.
+/// Infinite games go through `infinite::choose_move` instead.
+pub(crate) fn choose_move(
+    pos: &mut Bitboard,
+    strength: Strength,
+    rules: Rules,
+    rng: &mut Rng,
+) -> Option<u8> {
+    if rules.terminal_score(pos).is_some() {
+        return None;
+    }
+    let legal: Vec<u8> = (0..9).filter(|square| pos.is_legal(*square)).collect();
+    let moves = match strength {
+        Strength::Random => legal,
+        Strength::DepthLimited(depth) => {
+            let scores = search_moves_to_depth(pos, depth, rules);
+            tied_best(pos, scores)
+        }
+        Strength::Blunder(probability) => {
+            let scores = move_scores(pos, rules);
+            // a slower win is still a win, only moves which change the result count as mistakes
+            let sign = if pos.current_player() { 1 } else { -1 };
+            let result = |score: i8| sign * score.signum();
+            let best_result = scores.iter().map(|(_, score)| result(*score)).max();
+            let mistakes: Vec<u8> = scores
+                .iter()
+                .filter(|(_, score)| Some(result(*score)) < best_result)
+                .map(|(square, _)| *square)
+                .collect();
+            if !mistakes.is_empty() && rng.chance(probability) {
+                mistakes
+            } else {
+                tied_best(pos, scores)
+            }
+        }
+        Strength::EpsilonOptimal(epsilon) => {
+            if rng.chance(epsilon) {
+                legal
+            } else {
+                optimal_moves(pos, rules)
+            }
+        }
+        Strength::Perfect => optimal_moves(pos, rules),
+    };
+    rng.choose(&moves).copied()
+}
+
+fn optimal_moves(pos: &mut Bitboard, rules: Rules) -> Vec<u8> {
+    let scores = move_scores(pos, rules);
+    tied_best(pos, scores)
+}
+
+// the exact score after each legal move, looked up in the tablebase where possible
+fn move_scores(pos: &mut Bitboard, rules: Rules) -> Vec<(u8, i8)> {
+    if rules == Rules::Standard {
+        let tablebase = Tablebase::global();
+        let scores: Option<Vec<(u8, i8)>> = (0..9)
+            .filter(|square| pos.is_legal(*square))
+            .map(|square| {
+                let mut child = *pos;
+                child.play(square);
+                tablebase.probe(&child).map(|entry| (square, entry.score))
+            })
+            .collect();
+        if let Some(scores) = scores {
+            return scores;
+        }
+    }
+    // misère and positions that can't be reached in a legal game have to be searched
+    search_moves(pos, rules)
+}
+
+// picks out the moves tied for the best score for the player to move
+fn tied_best(pos: &Bitboard, scores: Vec<(u8, i8)>) -> Vec<u8> {
+    let best = if pos.current_player() {
+        scores.iter().map(|(_, score)| *score).max()
+    } else {
+        scores.iter().map(|(_, score)| *score).min()
+    }
+    .unwrap();
+    scores
+        .into_iter()
+        .filter(|(_, score)| *score == best)
+        .map(|(square, _)| square)
+        .collect()
+}
@@ -0,0 +1,167 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::bitboards::Bitboard;
+
+// where each square ends up under each transform, in the order of `Transform::ALL`
+const SQUARE_MAPS: [[u8; 9]; 8] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8],
+    [2, 5, 8, 1, 4, 7, 0, 3, 6],
+    [8, 7, 6, 5, 4, 3, 2, 1, 0],
+    [6, 3, 0, 7, 4, 1, 8, 5, 2],
+    [2, 1, 0, 5, 4, 3, 8, 7, 6],
+    [6, 7, 8, 3, 4, 5, 0, 1, 2],
+    [0, 3, 6, 1, 4, 7, 2, 5, 8],
+    [8, 5, 2, 7, 4, 1, 6, 3, 0],
+];
+
+/// One of the 8 symmetries of the board (the dihedral group D4).
+/// Rotations are clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirrors the board left to right.
+    FlipHorizontal,
+    /// Mirrors the board top to bottom.
+    FlipVertical,
+    /// Mirrors the board along the 0-4-8 diagonal.
+    FlipDiagonal,
+    /// Mirrors the board along the 2-4-6 diagonal.
+    FlipAntiDiagonal,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Identity,
+        Transform::Rotate90,
+        Transform::Rotate180,
+        Transform::Rotate270,
+        Transform::FlipHorizontal,
+        Transform::FlipVertical,
+        Transform::FlipDiagonal,
+        Transform::FlipAntiDiagonal,
+    ];
+
+    /// Returns the transform which undoes this one.
+    pub fn inverse(self) -> Transform {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            t => t,
+        }
+    }
+
+    /// Returns the square that `square` is moved to by this transform.
+    #[inline(always)]
+    pub fn apply_square(self, square: u8) -> u8 {
+        SQUARE_MAPS[self as usize][square as usize]
+    }
+
+    /// Maps a square on the transformed board back to the original board,
+    /// e.g. to turn a move found on the canonical position into a move on the real one.
+    #[inline(always)]
+    pub fn invert_square(self, square: u8) -> u8 {
+        self.inverse().apply_square(square)
+    }
+}
+
+impl Bitboard {
+    /// Returns the position after applying the transform to every square.
+    pub fn transform(&self, transform: Transform) -> Bitboard {
+        let mut out = Bitboard::new();
+        for square in 0..9 {
+            let to = transform.apply_square(square);
+            if self.x_bitboard & (1 << square) == 1 << square {
+                out.x_bitboard |= 1 << to;
+            }
+            if self.o_bitboard & (1 << square) == 1 << square {
+                out.o_bitboard |= 1 << to;
+            }
+        }
+        out
+    }
+
+    /// Returns the position rotated 90 degrees clockwise.
+    pub fn rotate(&self) -> Bitboard {
+        self.transform(Transform::Rotate90)
+    }
+
+    /// Returns the position mirrored left to right.
+    pub fn reflect(&self) -> Bitboard {
+        self.transform(Transform::FlipHorizontal)
+    }
+
+    /// Returns the canonical form of the position along with the transform that produced it,
+    /// such that `pos.transform(t) == canonical`.
+    /// Every symmetric equivalent of a position has the same canonical form,
+    /// which cuts the 5478 legal positions down to 765.
+    pub fn canonical(&self) -> (Bitboard, Transform) {
+        let mut best = (*self, Transform::Identity);
+        for transform in &Transform::ALL[1..] {
+            let candidate = self.transform(*transform);
+            if (candidate.x_bitboard, candidate.o_bitboard) < (best.0.x_bitboard, best.0.o_bitboard)
+            {
+                best = (candidate, *transform);
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitboards::legal_positions;
+    use std::collections::HashSet;
+
+    #[test]
+    fn squares_round_trip() {
+        for transform in Transform::ALL {
+            for square in 0..9 {
+                assert_eq!(
+                    transform.invert_square(transform.apply_square(square)),
+                    square
+                );
+                assert_eq!(
+                    transform.apply_square(transform.invert_square(square)),
+                    square
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let mut classes = HashSet::new();
+        for pos in legal_positions() {
+            let (canonical, transform) = pos.canonical();
+            assert_eq!(pos.transform(transform), canonical);
+            for other in Transform::ALL {
+                assert_eq!(pos.transform(other).canonical().0, canonical);
+            }
+            classes.insert(canonical);
+        }
+        assert_eq!(classes.len(), 765);
+    }
+}
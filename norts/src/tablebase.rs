@@ -0,0 +1,254 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::sync::OnceLock;
+
+use crate::bitboards::Bitboard;
+use crate::evaluation::{Evaluation, Square};
+use crate::rules::Rules;
+use crate::search::search;
+
+// every way of filling 9 squares with X, O or nothing
+const NUM_RANKS: usize = 19683;
+// marks positions which can't be reached from the starting position
+const UNREACHABLE: i8 = i8::MIN;
+
+static TABLEBASE: OnceLock<Tablebase> = OnceLock::new();
+
+/// The stored result for a single position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TablebaseEntry {
+    /// Eval in the same form returned by the search, `100 - game length` for an X win
+    /// and `-100 + game length` for an O win.
+    pub score: i8,
+    /// Bit `n` is set if square `n` is an optimal move.
+    pub best_moves: u16,
+    num_moves: u8,
+}
+
+impl TablebaseEntry {
+    pub fn evaluation(&self) -> Evaluation {
+        Evaluation::from_score(self.score, self.num_moves)
+    }
+
+    /// Returns the lowest numbered optimal move, or `None` if the game has ended.
+    pub fn best_move(&self) -> Option<Square> {
+        Square::new(self.best_moves.trailing_zeros() as u8)
+    }
+
+    /// Returns every optimal move in square order.
+    pub fn all_best_moves(&self) -> Vec<Square> {
+        (0..9)
+            .filter(|square| self.best_moves & (1 << square) != 0)
+            .map(|square| Square::new(square).unwrap())
+            .collect()
+    }
+}
+
+/// The exact value and optimal moves of every legal position under standard rules, so that positions
+/// can be answered with a lookup instead of a search.
+/// Positions are indexed by their base 3 rank, see `Tablebase::rank`,
+/// so the whole table takes up a little under 60KB.
+/// ``` ignore
+/// use norts::{Bitboard, Tablebase};
+///
+/// fn main() {
+///     let entry = Tablebase::global().probe(&Bitboard::new()).unwrap();
+///     println!("{} by playing {}", entry.evaluation(), entry.best_move().unwrap());
+/// }
+/// ```
+pub struct Tablebase {
+    scores: Vec<i8>,
+    best_moves: Vec<u16>,
+    len: usize,
+}
+
+impl Tablebase {
+    /// Returns the shared tablebase, generating it on first use.
+    pub fn global() -> &'static Tablebase {
+        TABLEBASE.get_or_init(Tablebase::generate)
+    }
+
+    /// Builds the tablebase by retrograde analysis.
+    /// Every reachable position is enumerated one move at a time from the start,
+    /// then positions are solved from the last move backwards, so each position
+    /// only needs the already solved positions one move after it.
+    pub fn generate() -> Tablebase {
+        let mut layers: Vec<Vec<Bitboard>> = vec![vec![Bitboard::new()]];
+        let mut seen = vec![false; NUM_RANKS];
+        seen[0] = true;
+        for num_moves in 0..9 {
+            let mut next = Vec::new();
+            for pos in &layers[num_moves] {
+                if pos.x_won() || pos.o_won() {
+                    continue;
+                }
+                for square in 0..9 {
+                    if !pos.is_legal(square) {
+                        continue;
+                    }
+                    let mut child = *pos;
+                    child.play(square);
+                    let rank = Tablebase::rank(&child);
+                    if !seen[rank] {
+                        seen[rank] = true;
+                        next.push(child);
+                    }
+                }
+            }
+            layers.push(next);
+        }
+
+        let mut scores = vec![UNREACHABLE; NUM_RANKS];
+        let mut best_moves = vec![0; NUM_RANKS];
+        let mut len = 0;
+        for layer in layers.iter().rev() {
+            for pos in layer {
+                let rank = Tablebase::rank(pos);
+                len += 1;
+                if pos.x_won() {
+                    scores[rank] = 100 - pos.num_moves() as i8;
+                    continue;
+                }
+                if pos.o_won() {
+                    scores[rank] = -100 + pos.num_moves() as i8;
+                    continue;
+                }
+                if pos.is_draw() {
+                    scores[rank] = 0;
+                    continue;
+                }
+                let x_to_move = pos.current_player();
+                let mut best = if x_to_move {
+                    i8::MIN + 10
+                } else {
+                    i8::MAX - 10
+                };
+                let mut moves: u16 = 0;
+                for square in 0..9 {
+                    if !pos.is_legal(square) {
+                        continue;
+                    }
+                    let mut child = *pos;
+                    child.play(square);
+                    let score = scores[Tablebase::rank(&child)];
+                    if score == best {
+                        moves |= 1 << square;
+                    } else if (x_to_move && score > best) || (!x_to_move && score < best) {
+                        best = score;
+                        moves = 1 << square;
+                    }
+                }
+                scores[rank] = best;
+                best_moves[rank] = moves;
+            }
+        }
+        Tablebase {
+            scores,
+            best_moves,
+            len,
+        }
+    }
+
+    /// Returns the index of a position in the table, reading each square as a base 3 digit
+    /// which is 0 for empty, 1 for X and 2 for O, with square 0 as the least significant digit.
+    pub fn rank(pos: &Bitboard) -> usize {
+        let mut rank = 0;
+        for square in (0..9).rev() {
+            rank *= 3;
+            if pos.x_bitboard & (1 << square) != 0 {
+                rank += 1;
+            } else if pos.o_bitboard & (1 << square) != 0 {
+                rank += 2;
+            }
+        }
+        rank
+    }
+
+    /// Looks up a position, returning `None` if it can't be reached in a legal game.
+    #[inline(always)]
+    pub fn probe(&self, pos: &Bitboard) -> Option<TablebaseEntry> {
+        let rank = Tablebase::rank(pos);
+        if self.scores[rank] == UNREACHABLE {
+            return None;
+        }
+        Some(TablebaseEntry {
+            score: self.scores[rank],
+            best_moves: self.best_moves[rank],
+            num_moves: pos.num_moves(),
+        })
+    }
+
+    /// Returns the number of legal positions stored, which should be 5478.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Checks every position against `search`, returning the first position where
+    /// the score differs or the move chosen by the search is not one of the stored best moves.
+    pub fn verify(&self) -> Result<(), Bitboard> {
+        for rank in 0..NUM_RANKS {
+            if self.scores[rank] == UNREACHABLE {
+                continue;
+            }
+            let mut pos = Tablebase::unrank(rank);
+            let (score, best_move) = search(&mut pos, i8::MIN, i8::MAX, Rules::Standard);
+            let move_ok = if best_move < 9 {
+                self.best_moves[rank] & (1 << best_move) != 0
+            } else {
+                self.best_moves[rank] == 0
+            };
+            if score != self.scores[rank] || !move_ok {
+                return Err(pos);
+            }
+        }
+        Ok(())
+    }
+
+    fn unrank(mut rank: usize) -> Bitboard {
+        let mut pos = Bitboard::new();
+        for square in 0..9 {
+            match rank % 3 {
+                1 => pos.x_bitboard |= 1 << square,
+                2 => pos.o_bitboard |= 1 << square,
+                _ => (),
+            }
+            rank /= 3;
+        }
+        pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn agrees_with_search() {
+        let tablebase = Tablebase::generate();
+        assert_eq!(tablebase.len(), 5478);
+        assert_eq!(tablebase.verify(), Ok(()));
+    }
+}
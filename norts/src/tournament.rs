@@ -0,0 +1,286 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::evaluation::Outcome;
+use crate::game::{play_out, Engine, Game};
+
+// z score for a 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// Wins, draws and losses for one engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Returns the points scored per game, counting a draw as half a win, or 0.5 if no games were played.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    fn add(&mut self, points: f64) {
+        match points {
+            p if p > 0.5 => self.wins += 1,
+            p if p < 0.5 => self.losses += 1,
+            _ => self.draws += 1,
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} ={} -{} ({:.1}%)",
+            self.wins,
+            self.draws,
+            self.losses,
+            self.score() * 100.0
+        )
+    }
+}
+
+/// An estimate of how many Elo points one engine is stronger than another, with a 95% confidence interval.
+/// Bounds are infinite when every game in them was won or lost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EloEstimate {
+    pub difference: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl fmt::Display for EloEstimate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:+.1} (95% confidence {:+.1} to {:+.1})",
+            self.difference, self.lower, self.upper
+        )
+    }
+}
+
+/// The results of `play_match`, all from the point of view of the first engine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    pub total: Record,
+    /// Games where the first engine played X, or the first player in games without X and O.
+    /// That is every other game starting with the first, even when the opening leaves O to move.
+    pub as_first: Record,
+    /// Games where the first engine played O, or the second player.
+    pub as_second: Record,
+}
+
+impl MatchResult {
+    /// Estimates the Elo difference between the engines from the score,
+    /// with the confidence interval taken from the spread of the individual game results.
+    pub fn elo_difference(&self) -> EloEstimate {
+        let games = self.total.games() as f64;
+        let score = self.total.score();
+        if games == 0.0 {
+            return EloEstimate {
+                difference: 0.0,
+                lower: f64::NEG_INFINITY,
+                upper: f64::INFINITY,
+            };
+        }
+        let variance = (self.total.wins as f64 * (1.0 - score).powi(2)
+            + self.total.draws as f64 * (0.5 - score).powi(2)
+            + self.total.losses as f64 * score.powi(2))
+            / games;
+        let margin = Z_95 * (variance / games).sqrt();
+        EloEstimate {
+            difference: elo(score),
+            lower: elo(score - margin),
+            upper: elo(score + margin),
+        }
+    }
+}
+
+impl fmt::Display for MatchResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Games:          {}", self.total.games())?;
+        writeln!(f, "Total:          {}", self.total)?;
+        writeln!(f, "Playing X:      {}", self.as_first)?;
+        writeln!(f, "Playing O:      {}", self.as_second)?;
+        write!(f, "Elo difference: {}", self.elo_difference())
+    }
+}
+
+// converts an expected score into an Elo difference
+fn elo(score: f64) -> f64 {
+    if score <= 0.0 {
+        f64::NEG_INFINITY
+    } else if score >= 1.0 {
+        f64::INFINITY
+    } else {
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+}
+
+/// Plays `games` games between two engines and returns the results from the point of view of `engine_a`.
+/// `engine_a` plays X in the first game, `engine_b` plays X in the second, and so on.
+/// Games start from each of `openings` in turn, and each opening is played twice in a row so both engines get each side.
+/// To play every game from the start, pass the starting position as the only opening.
+/// ``` ignore
+/// use norts::{play_match, Board, Strength, StrengthPlayer};
+///
+/// fn main() {
+///     let mut medium = StrengthPlayer::new(Strength::DepthLimited(2), 1);
+///     let mut random = StrengthPlayer::new(Strength::Random, 2);
+///     let openings = [Board::new(), Board::parse_pgn("4").unwrap()];
+///     println!("{}", play_match(&mut medium, &mut random, &openings, 100));
+/// }
+/// ```
+pub fn play_match<G: Game>(
+    engine_a: &mut dyn Engine<G>,
+    engine_b: &mut dyn Engine<G>,
+    openings: &[G],
+    games: u32,
+) -> MatchResult {
+    let mut result = MatchResult::default();
+    if openings.is_empty() {
+        return result;
+    }
+    for game in 0..games {
+        let opening = &openings[(game as usize / 2) % openings.len()];
+        let a_first = game % 2 == 0;
+        let (end, legal) = if a_first {
+            play_out(opening, engine_a, engine_b)
+        } else {
+            play_out(opening, engine_b, engine_a)
+        };
+        let first_points = if !legal {
+            // an engine which chooses an illegal move loses the game
+            if end.current_player() == 1 {
+                0.0
+            } else {
+                1.0
+            }
+        } else {
+            match end.situation() {
+                Outcome::XWon => 1.0,
+                Outcome::OWon => 0.0,
+                _ => 0.5,
+            }
+        };
+        if a_first {
+            result.total.add(first_points);
+            result.as_first.add(first_points);
+        } else {
+            result.total.add(1.0 - first_points);
+            result.as_second.add(1.0 - first_points);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::player::StrengthPlayer;
+
+    #[test]
+    fn results_by_colour() {
+        // X to move and win by playing 2
+        let x_wins = Board::parse_pgn("0314").unwrap();
+        let mut a = StrengthPlayer::perfect(1);
+        let mut b = StrengthPlayer::perfect(2);
+        let result = play_match(&mut a, &mut b, &[x_wins], 4);
+        let won = |wins| Record {
+            wins,
+            draws: 0,
+            losses: 0,
+        };
+        let lost = |losses| Record {
+            wins: 0,
+            draws: 0,
+            losses,
+        };
+        assert_eq!(result.as_first, won(2));
+        assert_eq!(result.as_second, lost(2));
+
+        // O moves next and wins by playing 5, but the engine playing X is still counted in `as_first`
+        let o_wins = Board::parse_pgn("03841").unwrap();
+        let result = play_match(&mut a, &mut b, &[o_wins], 4);
+        assert_eq!(result.as_first, lost(2));
+        assert_eq!(result.as_second, won(2));
+        assert_eq!(
+            result.total,
+            Record {
+                wins: 2,
+                draws: 0,
+                losses: 2
+            }
+        );
+
+        let result = play_match(&mut a, &mut b, &[Board::new()], 6);
+        assert_eq!(result.total.draws, 6);
+        assert_eq!(result.as_first.games(), 3);
+        assert_eq!(result.as_second.games(), 3);
+    }
+
+    #[test]
+    fn elo_difference() {
+        let result = MatchResult {
+            total: Record {
+                wins: 6,
+                draws: 2,
+                losses: 2,
+            },
+            ..MatchResult::default()
+        };
+        // a score of 0.7, with a variance of (6 * 0.3^2 + 2 * 0.2^2 + 2 * 0.7^2) / 10 = 0.16 per game,
+        // so the interval is 0.7 ± 1.96 * sqrt(0.16 / 10)
+        let estimate = result.elo_difference();
+        assert!((estimate.difference - 147.19).abs() < 0.01);
+        assert!((estimate.lower - -33.40).abs() < 0.01);
+        assert!((estimate.upper - 504.05).abs() < 0.01);
+
+        let estimate = MatchResult::default().elo_difference();
+        assert_eq!(estimate.difference, 0.0);
+        assert_eq!(estimate.lower, f64::NEG_INFINITY);
+        assert_eq!(estimate.upper, f64::INFINITY);
+
+        let even = MatchResult {
+            total: Record {
+                wins: 0,
+                draws: 5,
+                losses: 0,
+            },
+            ..MatchResult::default()
+        };
+        assert_eq!(even.elo_difference().difference, 0.0);
+    }
+}
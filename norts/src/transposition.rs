@@ -0,0 +1,328 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+
+use crate::bitboards::Bitboard;
+use crate::symmetry::Transform;
+
+// magic bytes at the start of a saved table
+const MAGIC: &[u8; 4] = b"NRTT";
+// every position reachable in a legal game
+const LEGAL_POSITIONS: usize = 5478;
+
+/// How a stored eval relates to the true minimax value of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The eval is the exact value of the position.
+    Exact,
+    /// The search failed high, the true value is at least the eval.
+    Lower,
+    /// The search failed low, the true value is at most the eval.
+    Upper,
+}
+
+/// A cached search result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub eval: i8,
+    pub best_move: u8,
+    pub bound: Bound,
+}
+
+/// Counters describing how useful the table has been.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableStats {
+    /// Number of probes which found an entry for the position.
+    pub hits: u64,
+    /// Number of probes which found nothing.
+    pub misses: u64,
+    /// Number of entries written.
+    pub stores: u64,
+}
+
+/// Caches search results keyed on the position, so positions reached
+/// by different move orders only have to be solved once.
+/// The table is owned by a `Searcher` and kept between calls until it is cleared.
+pub struct TranspositionTable {
+    entries: HashMap<Bitboard, Entry>,
+    stats: TableStats,
+    // whether positions are stored under their canonical form
+    symmetric: bool,
+}
+
+impl TranspositionTable {
+    /// Returns an empty table.
+    pub fn new() -> TranspositionTable {
+        TranspositionTable {
+            entries: HashMap::new(),
+            stats: TableStats::default(),
+            symmetric: false,
+        }
+    }
+
+    /// Returns an empty table which stores every position under its canonical form,
+    /// so a result found for one position is reused for all 8 of its symmetries.
+    pub fn with_symmetry() -> TranspositionTable {
+        TranspositionTable {
+            symmetric: true,
+            ..TranspositionTable::new()
+        }
+    }
+
+    /// Returns an empty table with room for `capacity` positions.
+    /// There are only 5478 legal positions so this is the most a table will ever need.
+    pub fn with_capacity(capacity: usize) -> TranspositionTable {
+        TranspositionTable {
+            entries: HashMap::with_capacity(capacity),
+            stats: TableStats::default(),
+            symmetric: false,
+        }
+    }
+
+    /// Looks up a position, recording a hit or a miss.
+    pub fn probe(&mut self, pos: &Bitboard) -> Option<Entry> {
+        let entry = if self.symmetric {
+            let (canonical, transform) = pos.canonical();
+            self.entries.get(&canonical).map(|entry| Entry {
+                best_move: map_move(entry.best_move, transform.inverse()),
+                ..*entry
+            })
+        } else {
+            self.entries.get(pos).copied()
+        };
+        if entry.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        entry
+    }
+
+    /// Stores a result for a position, replacing anything already there.
+    pub fn store(&mut self, pos: Bitboard, entry: Entry) {
+        self.stats.stores += 1;
+        if self.symmetric {
+            let (canonical, transform) = pos.canonical();
+            let entry = Entry {
+                best_move: map_move(entry.best_move, transform),
+                ..entry
+            };
+            self.entries.insert(canonical, entry);
+        } else {
+            self.entries.insert(pos, entry);
+        }
+    }
+
+    /// Returns whether positions are stored under their canonical form.
+    pub fn is_symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    /// Returns the number of positions stored.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn stats(&self) -> TableStats {
+        self.stats
+    }
+
+    /// Zeroes the statistics without touching the stored entries.
+    pub fn reset_stats(&mut self) {
+        self.stats = TableStats::default();
+    }
+
+    /// Removes every entry and zeroes the statistics.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.reset_stats();
+    }
+
+    /// Writes every entry to `writer` so the table can be reloaded with `TranspositionTable::load`.
+    /// Statistics are not saved.
+    pub fn save<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[self.symmetric as u8])?;
+        writer.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (pos, entry) in &self.entries {
+            writer.write_all(&pos.x_bitboard.to_le_bytes())?;
+            writer.write_all(&pos.o_bitboard.to_le_bytes())?;
+            let bound = match entry.bound {
+                Bound::Exact => 0,
+                Bound::Lower => 1,
+                Bound::Upper => 2,
+            };
+            writer.write_all(&[entry.eval as u8, entry.best_move, bound])?;
+        }
+        Ok(())
+    }
+
+    /// Reads a table previously written with `TranspositionTable::save`.
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<TranspositionTable> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a norts transposition table"));
+        }
+        let mut symmetric = [0; 1];
+        reader.read_exact(&mut symmetric)?;
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len) as usize;
+        // the length comes from the file, so it is only trusted as far as the number of legal positions
+        let mut table = TranspositionTable::with_capacity(len.min(LEGAL_POSITIONS));
+        table.symmetric = symmetric[0] != 0;
+        for _ in 0..len {
+            let mut buf = [0; 7];
+            reader.read_exact(&mut buf)?;
+            let pos = Bitboard {
+                x_bitboard: u16::from_le_bytes([buf[0], buf[1]]),
+                o_bitboard: u16::from_le_bytes([buf[2], buf[3]]),
+            };
+            if pos.x_bitboard & pos.o_bitboard != 0 || (pos.x_bitboard | pos.o_bitboard) >> 9 != 0 {
+                return Err(invalid_data("invalid position in transposition table"));
+            }
+            if table.symmetric && pos.canonical().0 != pos {
+                return Err(invalid_data(
+                    "position in transposition table is not canonical",
+                ));
+            }
+            let eval = buf[4] as i8;
+            // scores are at most 100 less the game length, see `Searcher::alpha_beta`
+            if eval.unsigned_abs() > 100 {
+                return Err(invalid_data("eval out of range in transposition table"));
+            }
+            // 9 means there is no move
+            let best_move = buf[5];
+            if best_move > 9 || (best_move < 9 && !pos.is_legal(best_move)) {
+                return Err(invalid_data("illegal best move in transposition table"));
+            }
+            let bound = match buf[6] {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                2 => Bound::Upper,
+                _ => return Err(invalid_data("unknown bound in transposition table")),
+            };
+            table.entries.insert(
+                pos,
+                Entry {
+                    eval,
+                    best_move,
+                    bound,
+                },
+            );
+        }
+        Ok(table)
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        TranspositionTable::new()
+    }
+}
+
+// 9 means there is no move so it is left alone
+fn map_move(square: u8, transform: Transform) -> u8 {
+    if square < 9 {
+        transform.apply_square(square)
+    } else {
+        square
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Searcher;
+
+    fn filled_table(mut searcher: Searcher) -> TranspositionTable {
+        searcher.search(&mut Bitboard::new());
+        searcher.take_table().unwrap()
+    }
+
+    #[test]
+    fn stats() {
+        let mut table = TranspositionTable::new();
+        let pos = Bitboard::new();
+        assert_eq!(table.probe(&pos), None);
+        let entry = Entry {
+            eval: 0,
+            best_move: 4,
+            bound: Bound::Exact,
+        };
+        table.store(pos, entry);
+        assert_eq!(table.probe(&pos), Some(entry));
+        let stats = table.stats();
+        assert_eq!((stats.hits, stats.misses, stats.stores), (1, 1, 1));
+        table.reset_stats();
+        assert_eq!(table.stats(), TableStats::default());
+        assert_eq!(table.len(), 1);
+    }
+
+    #[test]
+    fn save_and_load() {
+        for table in [
+            filled_table(Searcher::with_table()),
+            filled_table(Searcher::with_symmetric_table()),
+        ] {
+            let mut bytes = Vec::new();
+            table.save(&mut bytes).unwrap();
+            let loaded = TranspositionTable::load(&mut &bytes[..]).unwrap();
+            assert_eq!(loaded.is_symmetric(), table.is_symmetric());
+            assert_eq!(loaded.entries, table.entries);
+        }
+    }
+
+    #[test]
+    fn load_rejects_bad_entries() {
+        // a table holding one position with X's marks and no O, with the eval, best move and an exact bound
+        let file = |symmetric: u8, x: u16, eval: u8, best_move: u8| {
+            let mut bytes = b"NRTT".to_vec();
+            bytes.push(symmetric);
+            bytes.extend(1u32.to_le_bytes());
+            bytes.extend(x.to_le_bytes());
+            bytes.extend(0u16.to_le_bytes());
+            bytes.extend([eval, best_move, 0]);
+            bytes
+        };
+        assert!(TranspositionTable::load(&mut &file(0, 1, 0, 4)[..]).is_ok());
+        assert!(TranspositionTable::load(&mut &file(0, 1, 0, 9)[..]).is_ok());
+        // the square is taken
+        assert!(TranspositionTable::load(&mut &file(0, 1, 0, 0)[..]).is_err());
+        assert!(TranspositionTable::load(&mut &file(0, 1, 0, 10)[..]).is_err());
+        assert!(TranspositionTable::load(&mut &file(0, 1, 101, 4)[..]).is_err());
+        // X in square 2 is a mirror image of square 0
+        assert!(TranspositionTable::load(&mut &file(1, 4, 0, 4)[..]).is_err());
+        assert!(TranspositionTable::load(&mut &file(0, 4, 0, 4)[..]).is_ok());
+    }
+}
@@ -0,0 +1,346 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+
+use crate::bitboards::{has_line, Bitboard, FULL};
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::Outcome;
+use crate::ultimate_search::UltimateSearcher;
+
+/// A move in Ultimate tic-tac-toe: a square within one of the nine small boards.
+/// Both are numbered the same way as squares on a normal `Board`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UltimateMove {
+    pub board: u8,
+    pub square: u8,
+}
+
+impl fmt::Display for UltimateMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.board, self.square)
+    }
+}
+
+/// Ultimate tic-tac-toe, played on nine small boards arranged in a 3x3 meta-board.
+/// Winning a small board claims that square of the meta-board, and a line on the meta-board wins the game.
+/// Every move sends the opponent to the small board matching the square just played,
+/// unless that board is already won or full in which case they can play on any open board.
+/// ``` ignore
+/// use norts::{UltimateBoard, UltimateMove};
+///
+/// fn main() {
+///     let mut pos = UltimateBoard::parse_pgn("4440").unwrap();
+///     pos.show();
+///     println!("Best move: {}", pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct UltimateBoard {
+    /// The small boards, indexed like squares.
+    pub boards: [Bitboard; 9],
+    /// Small boards won by X and by O.
+    pub meta: Bitboard,
+    // small boards which are full without a winner
+    drawn: u16,
+    history: Vec<UltimateMove>,
+}
+
+impl UltimateBoard {
+    pub fn new() -> UltimateBoard {
+        UltimateBoard {
+            boards: [Bitboard::new(); 9],
+            meta: Bitboard::new(),
+            drawn: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns a board which picks up from the specified PGN string.
+    /// Each move is written as two digits, the small board followed by the square within it,
+    /// so "4440" is X in the middle of the centre board then O in the top left of the centre board,
+    /// which sends X to the top left board.
+    pub fn parse_pgn(pgn: &str) -> Result<UltimateBoard, InvalidPgnError> {
+        let digits: Vec<u8> = pgn
+            .chars()
+            .map(|c| c.to_digit(10).map(|d| d as u8).ok_or(InvalidPgnError))
+            .collect::<Result<_, _>>()?;
+        if digits.len() % 2 != 0 {
+            return Err(InvalidPgnError);
+        }
+        let mut pos = UltimateBoard::new();
+        for pair in digits.chunks(2) {
+            let mv = UltimateMove {
+                board: pair[0],
+                square: pair[1],
+            };
+            pos.play(mv).map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `UltimateBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history.iter().map(|mv| mv.to_string()).collect()
+    }
+
+    pub fn history(&self) -> &[UltimateMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if X is to move and -1 if O is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.x_to_move() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn x_to_move(&self) -> bool {
+        self.history.len() % 2 == 0
+    }
+
+    /// Returns true if the small board has been won or filled, so no more moves can be played on it.
+    #[inline(always)]
+    pub fn is_closed(&self, board: u8) -> bool {
+        (self.meta.x_bitboard | self.meta.o_bitboard | self.drawn) & (1 << board) != 0
+    }
+
+    /// Returns the small board the player to move has been sent to,
+    /// or `None` if they may play on any open board.
+    pub fn next_board(&self) -> Option<u8> {
+        match self.history.last() {
+            Some(last) if !self.is_closed(last.square) => Some(last.square),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid_move(&self, mv: UltimateMove) -> bool {
+        mv.board < 9
+            && mv.square < 9
+            && self.is_in_play()
+            && !self.is_closed(mv.board)
+            && self.next_board().map_or(true, |board| board == mv.board)
+            && self.boards[mv.board as usize].is_legal(mv.square)
+    }
+
+    /// Returns every legal move, ordered by board and then square.
+    pub fn legal_moves(&self) -> Vec<UltimateMove> {
+        let mut moves = Vec::new();
+        if !self.is_in_play() {
+            return moves;
+        }
+        let boards = match self.next_board() {
+            Some(board) => board..board + 1,
+            None => 0..9,
+        };
+        for board in boards {
+            if self.is_closed(board) {
+                continue;
+            }
+            for square in 0..9 {
+                if self.boards[board as usize].is_legal(square) {
+                    moves.push(UltimateMove { board, square });
+                }
+            }
+        }
+        moves
+    }
+
+    /// Plays a move, see `UltimateBoard` for which moves are allowed.
+    pub fn play(&mut self, mv: UltimateMove) -> Result<bool, IllegalMoveError> {
+        if self.is_valid_move(mv) {
+            self.play_unchecked(mv);
+            Ok(true)
+        } else {
+            Err(IllegalMoveError)
+        }
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        if self.history.is_empty() {
+            return Err(NoMoveToUndoError);
+        }
+        self.undo_unchecked();
+        Ok(())
+    }
+
+    pub(crate) fn play_unchecked(&mut self, mv: UltimateMove) {
+        let small = &mut self.boards[mv.board as usize];
+        if self.history.len() % 2 == 0 {
+            small.x_bitboard |= 1 << mv.square;
+        } else {
+            small.o_bitboard |= 1 << mv.square;
+        }
+        self.history.push(mv);
+        self.update_meta(mv.board);
+    }
+
+    pub(crate) fn undo_unchecked(&mut self) {
+        let mv = self.history.pop().unwrap();
+        self.boards[mv.board as usize].clear_square(mv.square);
+        self.update_meta(mv.board);
+    }
+
+    // recalculates whether a small board is won, drawn or open
+    fn update_meta(&mut self, board: u8) {
+        let bit = 1 << board;
+        self.meta.x_bitboard &= !bit;
+        self.meta.o_bitboard &= !bit;
+        self.drawn &= !bit;
+        let small = &self.boards[board as usize];
+        if has_line(small.x_bitboard) {
+            self.meta.x_bitboard |= bit;
+        } else if has_line(small.o_bitboard) {
+            self.meta.o_bitboard |= bit;
+        } else if small.x_bitboard | small.o_bitboard == FULL {
+            self.drawn |= bit;
+        }
+    }
+
+    /// Returns whether X or O has won, the game is drawn or it is still ongoing.
+    /// The game is drawn once every small board is closed without a line on the meta-board.
+    pub fn situation(&self) -> Outcome {
+        if has_line(self.meta.x_bitboard) {
+            Outcome::XWon
+        } else if has_line(self.meta.o_bitboard) {
+            Outcome::OWon
+        } else if self.meta.x_bitboard | self.meta.o_bitboard | self.drawn == FULL {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.situation() == Outcome::Ongoing
+    }
+
+    /// Returns the best move found by a default `UltimateSearcher`.
+    /// The game is far too big to solve, so this is a depth limited search.
+    pub fn best_move(&mut self) -> Result<UltimateMove, PositionAlreadyConcludedError> {
+        self.best_move_with(&mut UltimateSearcher::new())
+    }
+
+    /// Returns the best move found by the given searcher.
+    pub fn best_move_with(
+        &mut self,
+        searcher: &mut UltimateSearcher,
+    ) -> Result<UltimateMove, PositionAlreadyConcludedError> {
+        if self.is_in_play() {
+            Ok(searcher.search(self).best_move.unwrap())
+        } else {
+            Err(PositionAlreadyConcludedError)
+        }
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("*-------------------------------*");
+        print!("{}", self);
+        println!("*-------------------------------*");
+        match self.next_board() {
+            Some(board) => println!("Next move must be on board {}", board),
+            None => println!("Next move can be on any open board"),
+        }
+    }
+}
+
+impl Default for UltimateBoard {
+    fn default() -> Self {
+        UltimateBoard::new()
+    }
+}
+
+impl fmt::Display for UltimateBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for big_row in 0..3 {
+            if big_row > 0 {
+                writeln!(f, "---------+---------+---------")?;
+            }
+            for small_row in 0..3 {
+                for big_col in 0..3 {
+                    if big_col > 0 {
+                        write!(f, "|")?;
+                    }
+                    let small = &self.boards[big_row * 3 + big_col];
+                    for small_col in 0..3 {
+                        let square = small_row * 3 + small_col;
+                        if small.x_bitboard & (1 << square) != 0 {
+                            write!(f, " X ")?;
+                        } else if small.o_bitboard & (1 << square) != 0 {
+                            write!(f, " O ")?;
+                        } else {
+                            write!(f, " . ")?;
+                        }
+                    }
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_board_routing() {
+        let pos = UltimateBoard::new();
+        assert_eq!(pos.next_board(), None);
+        assert_eq!(pos.legal_moves().len(), 81);
+
+        // O's move to square 0 sends X to the top left board
+        let pos = UltimateBoard::parse_pgn("4440").unwrap();
+        assert_eq!(pos.next_board(), Some(0));
+        let moves = pos.legal_moves();
+        assert_eq!(moves.len(), 9);
+        assert!(moves.iter().all(|mv| mv.board == 0));
+        assert!(!pos.is_valid_move(UltimateMove {
+            board: 1,
+            square: 0
+        }));
+
+        // X wins the centre board, then O sends X back to it so X may play on any open board
+        let pos = UltimateBoard::parse_pgn("4004411442").unwrap();
+        assert!(pos.is_closed(4));
+        assert_eq!(pos.next_board(), Some(2));
+        let pos = UltimateBoard::parse_pgn("400441144224").unwrap();
+        assert_eq!(pos.next_board(), None);
+        let moves = pos.legal_moves();
+        assert_eq!(moves.len(), 72 - 3);
+        assert!(moves.iter().all(|mv| mv.board != 4));
+        assert!(UltimateBoard::parse_pgn("40044114422443").is_err());
+    }
+}
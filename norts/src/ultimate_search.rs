@@ -0,0 +1,185 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use crate::bitboards::{Bitboard, LINES};
+use crate::evaluation::Outcome;
+use crate::iterative::{IterativeSearch, Node, SearchPosition, WIN};
+use crate::ultimate::{UltimateBoard, UltimateMove};
+
+/// Nodes searched by a default `UltimateSearcher` before it settles for its last completed depth.
+pub const ULTIMATE_NODE_LIMIT: u64 = 500_000;
+
+// score for a meta-board line holding 0, 1 or 2 small boards won by one player and none by the other
+const META_LINE: [i32; 3] = [0, 40, 400];
+const SMALL_BOARD_WON: i32 = 100;
+// score for a small board line holding 0, 1 or 2 of one player's marks and none of the other's
+const SMALL_LINE: [i32; 3] = [0, 1, 6];
+
+/// The result of searching an `UltimateBoard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UltimateSearchResult {
+    pub best_move: Option<UltimateMove>,
+    /// Score from the point of view of the player to move, wins are ±1,000,000,000 less the length of the game.
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    pub nodes: u64,
+}
+
+/// Iterative deepening alpha-beta search for Ultimate tic-tac-toe with a transposition table.
+/// Positions at the depth limit are scored on the small boards won,
+/// lines on the meta-board and lines still open within each small board.
+pub struct UltimateSearcher {
+    search: IterativeSearch<([Bitboard; 9], Option<u8>), UltimateMove>,
+}
+
+impl UltimateSearcher {
+    /// Returns a searcher with no depth limit and a budget of `ULTIMATE_NODE_LIMIT` nodes per search.
+    pub fn new() -> UltimateSearcher {
+        UltimateSearcher {
+            search: IterativeSearch::new(ULTIMATE_NODE_LIMIT),
+        }
+    }
+
+    /// Limits how many moves ahead the search looks.
+    pub fn with_max_depth(mut self, depth: u8) -> UltimateSearcher {
+        self.search.max_depth = Some(depth);
+        self
+    }
+
+    /// Limits how many nodes each search may visit.
+    pub fn with_node_limit(mut self, nodes: u64) -> UltimateSearcher {
+        self.search.node_limit = nodes;
+        self
+    }
+
+    /// Empties the transposition table.
+    pub fn clear(&mut self) {
+        self.search.clear();
+    }
+
+    /// Searches the position, see `UltimateSearcher`.
+    pub fn search(&mut self, pos: &mut UltimateBoard) -> UltimateSearchResult {
+        self.search.reset();
+        if !pos.is_in_play() {
+            return UltimateSearchResult {
+                best_move: None,
+                score: 0,
+                depth: 0,
+                nodes: 0,
+            };
+        }
+        let iteration = self.search.search(pos, 81 - pos.num_moves());
+        UltimateSearchResult {
+            best_move: iteration.best_move,
+            score: iteration.score,
+            depth: iteration.depth,
+            nodes: self.search.nodes(),
+        }
+    }
+}
+
+impl Default for UltimateSearcher {
+    fn default() -> Self {
+        UltimateSearcher::new()
+    }
+}
+
+impl SearchPosition for UltimateBoard {
+    type Move = UltimateMove;
+    // the board to play on depends on the last move as well as the marks
+    type Key = ([Bitboard; 9], Option<u8>);
+
+    fn key(&self) -> Self::Key {
+        (self.boards, self.next_board())
+    }
+
+    fn mover(&self) -> bool {
+        self.x_to_move()
+    }
+
+    fn expand(&mut self, depth: u8) -> Node<UltimateMove> {
+        match self.situation() {
+            Outcome::Ongoing if depth == 0 => Node::Horizon(heuristic(self)),
+            Outcome::Ongoing => Node::Search,
+            Outcome::Draw => Node::Solved(0),
+            // the game is won by the player who just moved
+            _ => Node::Solved(-(WIN - self.num_moves() as i32)),
+        }
+    }
+
+    fn ordered_moves(&mut self) -> (Vec<UltimateMove>, bool) {
+        (self.legal_moves(), true)
+    }
+
+    fn play(&mut self, mv: UltimateMove) {
+        self.play_unchecked(mv);
+    }
+
+    fn undo(&mut self) {
+        self.undo_unchecked();
+    }
+}
+
+// scores the position from the point of view of the player to move
+fn heuristic(pos: &UltimateBoard) -> i32 {
+    let meta = &pos.meta;
+    let closed = (0..9).fold(0u16, |closed, board| {
+        if pos.is_closed(board) {
+            closed | 1 << board
+        } else {
+            closed
+        }
+    });
+    // drawn boards block meta-board lines for both players
+    let drawn = closed & !(meta.x_bitboard | meta.o_bitboard);
+    let mut score = (meta.x_bitboard.count_ones() as i32 - meta.o_bitboard.count_ones() as i32)
+        * SMALL_BOARD_WON;
+    score += line_score(meta, drawn, &META_LINE);
+    for board in 0..9 {
+        if closed & (1 << board) == 0 {
+            score += line_score(&pos.boards[board as usize], 0, &SMALL_LINE);
+        }
+    }
+    if pos.x_to_move() {
+        score
+    } else {
+        -score
+    }
+}
+
+// scores lines that only one player has marks in, from X's point of view
+fn line_score(pos: &Bitboard, blocked: u16, weights: &[i32; 3]) -> i32 {
+    let mut score = 0;
+    for line in LINES {
+        if line & blocked != 0 {
+            continue;
+        }
+        let x = (pos.x_bitboard & line).count_ones() as usize;
+        let o = (pos.o_bitboard & line).count_ones() as usize;
+        if o == 0 && x < 3 {
+            score += weights[x];
+        } else if x == 0 && o < 3 {
+            score -= weights[o];
+        }
+    }
+    score
+}
@@ -0,0 +1,362 @@
+/*
+Copyright (c) 2023. "MrPiggyPegasus"
+This file is part of the "norts" Noughts and Crosses engine, see https://github.com/MrPiggyPegasus/norts.
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND,EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NON INFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+*/
+
+use std::fmt;
+use std::fmt::Formatter;
+use std::sync::OnceLock;
+
+use crate::bitboards::{has_line, Bitboard, FULL};
+use crate::board::{
+    IllegalMoveError, InvalidPgnError, NoMoveToUndoError, PositionAlreadyConcludedError,
+};
+use crate::evaluation::{Evaluation, Outcome};
+use crate::tablebase::Tablebase;
+
+// every position solved, indexed by `Tablebase::rank`, see `WildBoard::evaluation`
+static WILD_SCORES: OnceLock<Vec<i8>> = OnceLock::new();
+const UNSOLVED: i8 = i8::MIN;
+
+/// The mark placed by a move in Wild tic-tac-toe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    X,
+    O,
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Symbol::X => write!(f, "x"),
+            Symbol::O => write!(f, "o"),
+        }
+    }
+}
+
+/// A move in Wild tic-tac-toe: a square, numbered like squares on a normal `Board`, and the symbol placed on it.
+/// Written as the square followed by the symbol, e.g. "4x".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WildMove {
+    pub square: u8,
+    pub symbol: Symbol,
+}
+
+impl fmt::Display for WildMove {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.square, self.symbol)
+    }
+}
+
+/// Wild tic-tac-toe, where either player may place either symbol and whoever completes a line of either wins.
+/// As the symbols no longer say whose turn it is, the turn is tracked by the number of moves played,
+/// the first player moving on even move numbers.
+///
+/// The whole game is solved on first use, so `evaluation` and `best_move` are lookups.
+/// Evaluations and outcomes use X for the first player and O for the second, whatever symbols they placed.
+/// ``` ignore
+/// use norts::WildBoard;
+///
+/// fn main() {
+///     let mut pos = WildBoard::parse_pgn("4o0x").unwrap();
+///     pos.show();
+///     println!("{}, best move {}", pos.evaluation().unwrap(), pos.best_move().unwrap());
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WildBoard {
+    pub bitboard: Bitboard,
+    history: Vec<WildMove>,
+}
+
+impl WildBoard {
+    pub fn new() -> WildBoard {
+        WildBoard {
+            bitboard: Bitboard::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// Returns a board which picks up from the specified PGN string,
+    /// a list of moves each written as the square then "x" or "o", such as "4o0x".
+    pub fn parse_pgn(pgn: &str) -> Result<WildBoard, InvalidPgnError> {
+        let chars: Vec<char> = pgn.chars().filter(|c| !c.is_whitespace()).collect();
+        if chars.len() % 2 != 0 {
+            return Err(InvalidPgnError);
+        }
+        let mut pos = WildBoard::new();
+        for pair in chars.chunks(2) {
+            let square = pair[0].to_digit(10).ok_or(InvalidPgnError)? as u8;
+            let symbol = match pair[1].to_ascii_lowercase() {
+                'x' => Symbol::X,
+                'o' => Symbol::O,
+                _ => return Err(InvalidPgnError),
+            };
+            pos.play(WildMove { square, symbol })
+                .map_err(|_| InvalidPgnError)?;
+        }
+        Ok(pos)
+    }
+
+    /// Returns the moves played so far, see `WildBoard::parse_pgn`.
+    pub fn pgn(&self) -> String {
+        self.history.iter().map(|mv| mv.to_string()).collect()
+    }
+
+    pub fn history(&self) -> &[WildMove] {
+        &self.history
+    }
+
+    pub fn num_moves(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Returns 1 if the first player is to move and -1 if the second player is to move.
+    pub fn current_player(&self) -> i8 {
+        if self.history.len() % 2 == 0 {
+            1
+        } else {
+            -1
+        }
+    }
+
+    pub fn is_valid_move(&self, mv: WildMove) -> bool {
+        mv.square < 9 && self.bitboard.is_legal(mv.square) && self.is_in_play()
+    }
+
+    /// Returns every legal move, both symbols on each empty square.
+    pub fn legal_moves(&self) -> Vec<WildMove> {
+        if !self.is_in_play() {
+            return Vec::new();
+        }
+        (0..9)
+            .filter(|square| self.bitboard.is_legal(*square))
+            .flat_map(|square| [Symbol::X, Symbol::O].map(|symbol| WildMove { square, symbol }))
+            .collect()
+    }
+
+    pub fn play(&mut self, mv: WildMove) -> Result<bool, IllegalMoveError> {
+        if !self.is_valid_move(mv) {
+            return Err(IllegalMoveError);
+        }
+        self.bitboard = place(self.bitboard, mv);
+        self.history.push(mv);
+        Ok(true)
+    }
+
+    /// Undoes the last move that was played
+    pub fn undo_move(&mut self) -> Result<(), NoMoveToUndoError> {
+        let mv = self.history.pop().ok_or(NoMoveToUndoError)?;
+        self.bitboard.clear_square(mv.square);
+        Ok(())
+    }
+
+    /// Returns `Outcome::XWon` if the first player won and `Outcome::OWon` if the second player won,
+    /// whichever symbol completed the line.
+    pub fn situation(&self) -> Outcome {
+        if has_any_line(&self.bitboard) {
+            // the player who just moved completed the line
+            if self.current_player() == 1 {
+                Outcome::OWon
+            } else {
+                Outcome::XWon
+            }
+        } else if self.bitboard.is_draw() {
+            Outcome::Draw
+        } else {
+            Outcome::Ongoing
+        }
+    }
+
+    /// Returns a bool indicating whether or not the game has ended.
+    pub fn is_in_play(&self) -> bool {
+        self.situation() == Outcome::Ongoing
+    }
+
+    /// Returns the value of the position with perfect play,
+    /// where X wins for the first player and O wins for the second.
+    pub fn evaluation(&self) -> Result<Evaluation, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let score = wild_scores()[Tablebase::rank(&self.bitboard)];
+        Ok(Evaluation::from_score(score, self.num_moves() as u8))
+    }
+
+    /// Returns every move which keeps the best result for the player to move.
+    pub fn best_moves(&self) -> Result<Vec<WildMove>, PositionAlreadyConcludedError> {
+        if !self.is_in_play() {
+            return Err(PositionAlreadyConcludedError);
+        }
+        let scores = wild_scores();
+        let first_to_move = self.current_player() == 1;
+        // scores are from the first player's point of view
+        let player_score = |mv: &WildMove| {
+            let score = scores[Tablebase::rank(&place(self.bitboard, *mv))];
+            if first_to_move {
+                score
+            } else {
+                -score
+            }
+        };
+        let moves = self.legal_moves();
+        let best = moves.iter().map(player_score).max().unwrap();
+        Ok(moves
+            .into_iter()
+            .filter(|mv| player_score(mv) == best)
+            .collect())
+    }
+
+    /// Returns the first of the best moves, see `WildBoard::best_moves`.
+    pub fn best_move(&self) -> Result<WildMove, PositionAlreadyConcludedError> {
+        Ok(self.best_moves()?[0])
+    }
+
+    /// Displays a visual representation of the board to the standard output.
+    pub fn show(&self) {
+        println!("*-----------------------*");
+        println!(" Board:         Squares:");
+        let rendered = self.to_string();
+        for (row, line) in rendered.lines().enumerate() {
+            println!(
+                "{}       {}  {}  {}  ",
+                line,
+                row * 3,
+                row * 3 + 1,
+                row * 3 + 2
+            );
+        }
+        let player = if self.current_player() == 1 {
+            "first"
+        } else {
+            "second"
+        };
+        println!(" The {} player is to move", player);
+        println!("*-----------------------*");
+    }
+}
+
+impl Default for WildBoard {
+    fn default() -> Self {
+        WildBoard::new()
+    }
+}
+
+impl fmt::Display for WildBoard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in 0..3 {
+            for col in 0..3 {
+                let square = row * 3 + col;
+                if self.bitboard.x_bitboard & (1 << square) != 0 {
+                    write!(f, " X ")?;
+                } else if self.bitboard.o_bitboard & (1 << square) != 0 {
+                    write!(f, " O ")?;
+                } else {
+                    write!(f, " . ")?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn place(mut pos: Bitboard, mv: WildMove) -> Bitboard {
+    match mv.symbol {
+        Symbol::X => pos.x_bitboard |= 1 << mv.square,
+        Symbol::O => pos.o_bitboard |= 1 << mv.square,
+    }
+    pos
+}
+
+#[inline(always)]
+fn has_any_line(pos: &Bitboard) -> bool {
+    has_line(pos.x_bitboard) || has_line(pos.o_bitboard)
+}
+
+fn wild_scores() -> &'static [i8] {
+    WILD_SCORES.get_or_init(|| {
+        let mut scores = vec![UNSOLVED; 19683];
+        solve(Bitboard::new(), &mut scores);
+        scores
+    })
+}
+
+// minimax over every reachable position, scored like `Tablebase` from the first player's point of view.
+// The turn is the number of marks on the board, as every move adds exactly one
+fn solve(pos: Bitboard, scores: &mut [i8]) -> i8 {
+    let rank = Tablebase::rank(&pos);
+    if scores[rank] != UNSOLVED {
+        return scores[rank];
+    }
+    let num_moves = (pos.x_bitboard | pos.o_bitboard).count_ones() as i8;
+    let first_to_move = num_moves % 2 == 0;
+    let score = if has_any_line(&pos) {
+        if first_to_move {
+            -100 + num_moves
+        } else {
+            100 - num_moves
+        }
+    } else if (pos.x_bitboard | pos.o_bitboard) == FULL {
+        0
+    } else {
+        let children = (0..9u8)
+            .filter(|square| pos.is_legal(*square))
+            .flat_map(|square| [Symbol::X, Symbol::O].map(|symbol| WildMove { square, symbol }))
+            .map(|mv| solve(place(pos, mv), scores));
+        if first_to_move {
+            children.max().unwrap()
+        } else {
+            children.min().unwrap()
+        }
+    };
+    scores[rank] = score;
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_player_wins() {
+        let mut pos = WildBoard::new();
+        assert_eq!(pos.evaluation().unwrap(), Evaluation::XWinsIn(7));
+        // the centre wins with either symbol
+        assert_eq!(
+            pos.best_moves().unwrap(),
+            vec![
+                WildMove {
+                    square: 4,
+                    symbol: Symbol::X
+                },
+                WildMove {
+                    square: 4,
+                    symbol: Symbol::O
+                },
+            ]
+        );
+        while pos.is_in_play() {
+            let mv = pos.best_move().unwrap();
+            pos.play(mv).unwrap();
+        }
+        assert_eq!(pos.situation(), Outcome::XWon);
+        assert_eq!(pos.num_moves(), 7);
+    }
+}